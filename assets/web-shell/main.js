@@ -0,0 +1,50 @@
+// Boson generic web shell
+//
+// Opens an HTML5 game's index.html in a window. Boson passes the web root and
+// the window options through environment variables.
+
+const { app, BrowserWindow } = require("electron");
+const path = require("node:path");
+const { pathToFileURL } = require("node:url");
+
+const webRoot =
+    process.env.BOSON_WEB_ROOT ||
+    process.argv.slice(2).find((arg) => !arg.startsWith("-"));
+
+if (!webRoot) {
+    console.error("BOSON: No web root given, set BOSON_WEB_ROOT");
+    process.exit(1);
+}
+
+const width = parseInt(process.env.BOSON_WEB_WIDTH || "1280", 10);
+const height = parseInt(process.env.BOSON_WEB_HEIGHT || "720", 10);
+const fullscreen = process.env.BOSON_WEB_FULLSCREEN === "1";
+const zoomFactor = parseFloat(process.env.BOSON_WEB_ZOOM || "1.0");
+
+console.log("BOSON: Opening web root", webRoot);
+
+// Web exports expect to be able to play audio and video right away
+app.commandLine.appendSwitch("autoplay-policy", "no-user-gesture-required");
+
+app.whenReady().then(() => {
+    const win = new BrowserWindow({
+        width,
+        height,
+        fullscreen,
+        useContentSize: true,
+        autoHideMenuBar: true,
+        webPreferences: {
+            // Steamworks bindings are loaded in the page through Node.js, as NW.js exports do
+            nodeIntegration: true,
+            contextIsolation: false,
+            preload: path.join(__dirname, "preload.js"),
+            zoomFactor,
+        },
+    });
+    win.setMenuBarVisibility(false);
+    win.loadURL(pathToFileURL(path.join(webRoot, "index.html")).href);
+});
+
+app.on("window-all-closed", () => {
+    app.quit();
+});
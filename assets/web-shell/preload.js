@@ -0,0 +1,17 @@
+// Forward Steamworks to the page through Boson's greenworks and steamworks.js overrides
+
+const path = require("node:path");
+const bosonDir = path.join(__dirname, "..");
+
+try {
+    require(path.join(bosonDir, "register-hook.js"));
+
+    // Web exports usually require greenworks by its bare name
+    const overrideRequire = require(path.join(bosonDir, "node_modules", "override-require"));
+    overrideRequire(
+        (request) => request === "greenworks",
+        () => require(path.join(bosonDir, "lib", "greenworks")),
+    );
+} catch (e) {
+    console.error("BOSON: Failed to load Steamworks hooks:", e);
+}
@@ -0,0 +1,301 @@
+//! ASAR archive reader
+//!
+//! Electron apps are usually packed into an `app.asar`: a Chromium pickle holding the header size,
+//! a pickle holding the JSON file index, then the file contents. Files can also be left out of
+//! the archive into `app.asar.unpacked`, which is what apps do with native `.node` modules.
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use stable_eyre::Result;
+
+/// An entry in the ASAR index
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Entry {
+    Directory {
+        files: BTreeMap<String, Entry>,
+    },
+    File {
+        size: u64,
+        /// Offset from the start of the file data, as a string since it can exceed 2^53
+        #[serde(default)]
+        offset: Option<String>,
+        /// The file lives in `<archive>.unpacked` instead
+        #[serde(default)]
+        unpacked: bool,
+    },
+    /// Symlink, relative to the archive root
+    Link {
+        link: String,
+    },
+}
+
+/// An opened ASAR archive, with its index read
+#[derive(Debug)]
+pub struct Archive {
+    path: PathBuf,
+    index: Entry,
+    /// Where the file data starts, right after the header
+    data_offset: u64,
+}
+
+fn read_u32(file: &mut File) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    file.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+impl Archive {
+    /// Open an archive and read its index
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+
+        // First pickle: payload size (always 4), then the size of the header pickle
+        let size_pickle_len = read_u32(&mut file)?;
+        if size_pickle_len != 4 {
+            return Err(stable_eyre::eyre::eyre!(
+                "{:?} is not an ASAR archive, unexpected size pickle length {}",
+                path,
+                size_pickle_len
+            ));
+        }
+        let header_size = read_u32(&mut file)? as u64;
+
+        // Second pickle: payload size, then the JSON string length and the string itself
+        let _header_payload_len = read_u32(&mut file)?;
+        let json_len = read_u32(&mut file)? as u64;
+        if json_len + 8 > header_size {
+            return Err(stable_eyre::eyre::eyre!(
+                "{:?} has an invalid ASAR header, index length {} exceeds header size {}",
+                path,
+                json_len,
+                header_size
+            ));
+        }
+        // Check against the actual file before allocating for it, sizes come from the file
+        let file_len = file.metadata()?.len();
+        if 16 + json_len > file_len {
+            return Err(stable_eyre::eyre::eyre!(
+                "{:?} has an invalid ASAR header, index length {} exceeds the file size {}",
+                path,
+                json_len,
+                file_len
+            ));
+        }
+        let mut json = vec![0u8; json_len as usize];
+        file.read_exact(&mut json)?;
+        let index: Entry = serde_json::from_slice(&json)?;
+        if !matches!(index, Entry::Directory { .. }) {
+            return Err(stable_eyre::eyre::eyre!(
+                "{:?} has an invalid ASAR index, the root is not a directory",
+                path
+            ));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            index,
+            data_offset: 8 + header_size,
+        })
+    }
+
+    /// Look up an entry by its path in the archive, e.g. `node_modules/foo/package.json`
+    pub fn entry(&self, path: &str) -> Option<&Entry> {
+        path.split(['/', '\\'])
+            .filter(|c| !c.is_empty() && *c != ".")
+            .try_fold(&self.index, |entry, component| match entry {
+                Entry::Directory { files } => files.get(component),
+                _ => None,
+            })
+    }
+
+    /// Read a file from the archive, or from `<archive>.unpacked` for unpacked files
+    pub fn read_file(&self, path: &str) -> Result<Vec<u8>> {
+        self.read_file_following(path, u64::MAX, 0)
+    }
+
+    /// Read at most the first `len` bytes of a file, e.g. to check its magic number
+    pub fn read_file_head(&self, path: &str, len: u64) -> Result<Vec<u8>> {
+        self.read_file_following(path, len, 0)
+    }
+
+    fn read_file_following(&self, path: &str, limit: u64, links_followed: u32) -> Result<Vec<u8>> {
+        const MAX_LINKS: u32 = 16;
+        match self.entry(path) {
+            Some(Entry::Link { link }) if links_followed < MAX_LINKS => {
+                self.read_file_following(link, limit, links_followed + 1)
+            }
+            Some(Entry::File { unpacked: true, .. }) => {
+                let mut unpacked = self.path.as_os_str().to_owned();
+                unpacked.push(".unpacked");
+                let mut data = Vec::new();
+                File::open(PathBuf::from(unpacked).join(path))?
+                    .take(limit)
+                    .read_to_end(&mut data)?;
+                Ok(data)
+            }
+            Some(Entry::File { size, offset, .. }) => {
+                let offset: u64 = offset.as_deref().unwrap_or("0").parse()?;
+                let mut file = File::open(&self.path)?;
+                let file_len = file.metadata()?.len();
+                let start = self.data_offset.checked_add(offset);
+                if start
+                    .and_then(|start| start.checked_add(*size))
+                    .is_none_or(|end| end > file_len)
+                {
+                    return Err(stable_eyre::eyre::eyre!(
+                        "{} in {:?} extends past the end of the archive",
+                        path,
+                        self.path
+                    ));
+                }
+                file.seek(SeekFrom::Start(self.data_offset + offset))?;
+                let mut data = Vec::new();
+                file.take((*size).min(limit)).read_to_end(&mut data)?;
+                Ok(data)
+            }
+            Some(_) => Err(stable_eyre::eyre::eyre!(
+                "{} is not a regular file in {:?}",
+                path,
+                self.path
+            )),
+            None => Err(stable_eyre::eyre::eyre!(
+                "{} not found in {:?}",
+                path,
+                self.path
+            )),
+        }
+    }
+
+    /// List every file in the archive, with `/` separated paths
+    pub fn files(&self) -> Vec<String> {
+        fn walk(entry: &Entry, prefix: &str, files: &mut Vec<String>) {
+            if let Entry::Directory { files: children } = entry {
+                for (name, child) in children {
+                    let path = if prefix.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{prefix}/{name}")
+                    };
+                    match child {
+                        Entry::Directory { .. } => walk(child, &path, files),
+                        _ => files.push(path),
+                    }
+                }
+            }
+        }
+
+        let mut files = Vec::new();
+        walk(&self.index, "", &mut files);
+        files
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build an ASAR archive from a list of files, like `@electron/asar` would
+    pub(crate) fn build_asar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut index = serde_json::json!({ "files": {} });
+        let mut data: Vec<u8> = Vec::new();
+        for (path, contents) in files {
+            let mut dir = &mut index;
+            let components: Vec<&str> = path.split('/').collect();
+            for component in &components[..components.len() - 1] {
+                dir = dir["files"]
+                    .as_object_mut()
+                    .unwrap()
+                    .entry(*component)
+                    .or_insert_with(|| serde_json::json!({ "files": {} }));
+            }
+            dir["files"][components[components.len() - 1]] = serde_json::json!({
+                "size": contents.len(),
+                "offset": data.len().to_string(),
+            });
+            data.extend(*contents);
+        }
+
+        let json = serde_json::to_vec(&index).unwrap();
+        let padding = (4 - json.len() % 4) % 4;
+        let header_payload_len = 4 + json.len() + padding;
+        let header_size = 4 + header_payload_len;
+
+        let mut bytes = Vec::new();
+        for field in [
+            4,
+            header_size as u32,
+            header_payload_len as u32,
+            json.len() as u32,
+        ] {
+            bytes.extend(field.to_le_bytes());
+        }
+        bytes.extend(&json);
+        bytes.extend(vec![0u8; padding]);
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn test_read_archive() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let asar = temp_dir.path().join("app.asar");
+        std::fs::write(
+            &asar,
+            build_asar(&[
+                (
+                    "package.json",
+                    br#"{"name": "game", "main": "src/main.js"}"#,
+                ),
+                ("src/main.js", b"require('electron')"),
+                ("node_modules/greenworks/package.json", b"{}"),
+            ]),
+        )
+        .unwrap();
+
+        let archive = Archive::open(&asar).unwrap();
+        assert_eq!(
+            archive.files(),
+            vec![
+                "node_modules/greenworks/package.json",
+                "package.json",
+                "src/main.js"
+            ]
+        );
+        assert_eq!(
+            archive.read_file("src/main.js").unwrap(),
+            b"require('electron')"
+        );
+        assert!(matches!(
+            archive.entry("src"),
+            Some(Entry::Directory { .. })
+        ));
+        assert!(archive.read_file("missing.js").is_err());
+
+        assert_eq!(archive.read_file_head("src/main.js", 2).unwrap(), b"re");
+
+        // Sizes that don't fit the file are rejected rather than allocated for
+        let mut bytes = build_asar(&[("big.bin", b"data")]);
+        let json_start = 16;
+        let json_len = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+        let json = String::from_utf8(bytes[json_start..json_start + json_len].to_vec()).unwrap();
+        let corrupt = json.replace(r#""size":4"#, r#""size":9"#);
+        assert_eq!(corrupt.len(), json.len());
+        bytes.splice(json_start..json_start + json_len, corrupt.into_bytes());
+        std::fs::write(&asar, &bytes).unwrap();
+        assert!(Archive::open(&asar).unwrap().read_file("big.bin").is_err());
+
+        bytes[12..16].copy_from_slice(&(u32::MAX - 8).to_le_bytes());
+        bytes[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&asar, &bytes).unwrap();
+        assert!(Archive::open(&asar).is_err());
+
+        std::fs::write(temp_dir.path().join("not.asar"), b"PK\x03\x04").unwrap();
+        assert!(Archive::open(&temp_dir.path().join("not.asar")).is_err());
+    }
+}
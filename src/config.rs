@@ -17,10 +17,13 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::path_search;
+use crate::{
+    path_search,
+    provenance::{self, ConfigSource, Provenance},
+};
 
 // Custom parser for reading 0/1 (and true/false) into a bool
-fn parse_bool(s: &str) -> std::result::Result<bool, String> {
+pub(crate) fn parse_bool(s: &str) -> std::result::Result<bool, String> {
     match s.trim().to_ascii_lowercase().as_str() {
         "1" | "true" | "yes" | "y" | "on" => Ok(true),
         "0" | "false" | "no" | "n" | "off" => Ok(false),
@@ -104,13 +107,49 @@ pub enum CompatType {
     /// Defer to another Steam compatibility tool, e.g., Proton
     DeferProton,
     /// Attempt native execution
-    /// Might be useful for .NET applications or cross-platform binaries
+    /// Might be useful for cross-platform binaries
     ForceNative,
 
     /// Electron-based games, Wrap the game with Boson's Electron runtime
     Electron,
+    /// NW.js-based games (RPG Maker MV/MZ, TyranoBuilder, Construct exports),
+    /// wrap the game with the host `nw` runtime
+    ///
+    /// Set `NWJS_PATH` to use a custom NW.js binary
+    NwJs,
+    /// Godot games, run the game's `.pck` data pack with a host Godot binary
+    /// matching the engine version, see `godot_binaries`
+    Godot,
+    /// Ren'Py games, run the game directory with a host Ren'Py SDK
+    /// matching the game's Ren'Py version, see `renpy_sdks`
+    RenPy,
+    /// .NET games built on XNA, FNA or MonoGame, run the main assembly with host `mono`
+    /// using the FNA libraries bundled with Boson (like MonoKickstart)
+    Mono,
+    /// Java games bundling a Windows JRE, run the game's jar with a host JRE
+    /// of the same major version, see `java_homes`
+    Java,
+    /// HashLink (Haxe) games, run `hlboot.dat` or the `.hl` bytecode with the host `hl`
+    /// and Linux builds of the native modules, see `hashlink_modules`
+    ///
+    /// Set `HASHLINK_PATH` to use a custom `hl` binary
+    HashLink,
+    /// RPG Maker XP/VX/VX Ace (RGSS) games, run with the host mkxp-z
+    /// using a generated `mkxp.json`, see `mkxp_rtps` and `mkxp_soundfont`
+    ///
+    /// Set `MKXP_PATH` to use a custom mkxp-z binary
+    Mkxp,
+    /// Flash projector games, extract the SWF and run it with the host Ruffle desktop player
+    /// see `ruffle_scale`, `ruffle_quality` and `ruffle_letterbox`
+    ///
+    /// Set `RUFFLE_PATH` to use a custom Ruffle binary
+    Flash,
+    /// HTML5 web exports (Construct, GDevelop, Twine), open the game's `index.html`
+    /// in Boson's generic Electron shell, see `web_width`, `web_height`, `web_fullscreen` and `web_zoom`
+    Web,
     /// LOVE2D games, wrap the game with `love` runtime
-    /// Requires `love` to be installed on the system
+    /// Requires `love` to be installed on the system, the binary matching the game's
+    /// LOVE version can be picked with `love_binaries`
     Love,
 }
 
@@ -131,6 +170,57 @@ impl CompatType {
                 disable_steam_overlay: true,
                 ..Default::default()
             },
+            CompatType::NwJs => GameConfig {
+                compat_type: CompatType::NwJs,
+                disable_steam_overlay: true,
+                ..Default::default()
+            },
+            CompatType::Godot => GameConfig {
+                compat_type: CompatType::Godot,
+                disable_steam_overlay: false,
+                ..Default::default()
+            },
+            CompatType::RenPy => GameConfig {
+                compat_type: CompatType::RenPy,
+                disable_steam_overlay: false,
+                ..Default::default()
+            },
+            CompatType::Mono => GameConfig {
+                compat_type: CompatType::Mono,
+                disable_steam_overlay: false,
+                ..Default::default()
+            },
+            CompatType::Java => GameConfig {
+                compat_type: CompatType::Java,
+                disable_steam_overlay: false,
+                ..Default::default()
+            },
+            CompatType::HashLink => GameConfig {
+                compat_type: CompatType::HashLink,
+                disable_steam_overlay: false,
+                ..Default::default()
+            },
+            CompatType::Mkxp => GameConfig {
+                compat_type: CompatType::Mkxp,
+                disable_steam_overlay: false,
+                // mkxp-z takes the game folder from mkxp.json, not the command line
+                arg_template: Some(vec![
+                    "%wrapper_args%".to_string(),
+                    "%steam_args%".to_string(),
+                    "%append_args%".to_string(),
+                ]),
+                ..Default::default()
+            },
+            CompatType::Flash => GameConfig {
+                compat_type: CompatType::Flash,
+                disable_steam_overlay: false,
+                ..Default::default()
+            },
+            CompatType::Web => GameConfig {
+                compat_type: CompatType::Web,
+                disable_steam_overlay: true,
+                ..Default::default()
+            },
             CompatType::Love => GameConfig {
                 compat_type: CompatType::Love,
                 disable_steam_overlay: false,
@@ -150,7 +240,8 @@ impl CompatType {
             // DeferProton is handled specially in runtime - returns empty defaults here
             CompatType::DeferProton => Ok((None, vec![])),
             CompatType::ForceNative => Ok((None, vec![])),
-            CompatType::Electron => {
+            // Web exports run in the same Electron runtime, just with our own main script
+            CompatType::Electron | CompatType::Web => {
                 let exec_dir = exec_path.parent().unwrap();
                 let electron = path_search::env_electron_path();
                 let mut args = vec!["--no-sandbox"];
@@ -161,10 +252,42 @@ impl CompatType {
                 args.extend(load_hook_arg);
                 Ok((Some(electron), args.iter().map(|s| s.to_string()).collect()))
             }
+            CompatType::NwJs => {
+                // Use system NW.js runtime
+                Ok((Some(path_search::env_nwjs_path()), vec![]))
+            }
+            CompatType::Godot => {
+                // The actual binary is picked per game from the pack version,
+                // this is only used as a last resort
+                let godot = crate::godot::env_godot_path().unwrap_or_else(|| "godot".to_string());
+                Ok((Some(godot), vec!["--main-pack".to_string()]))
+            }
+            // The SDK is picked per game from the configured SDKs, there's no default
+            CompatType::RenPy => Ok((None, vec![])),
+            CompatType::Mono => {
+                // Use system Mono runtime
+                Ok((Some("mono".to_string()), vec![]))
+            }
+            CompatType::Java => {
+                // The JRE is picked per game, this is only used as a last resort
+                Ok((Some("java".to_string()), vec![]))
+            }
+            CompatType::HashLink => {
+                // Use system HashLink VM
+                Ok((Some(crate::hashlink::env_hashlink_path()), vec![]))
+            }
+            CompatType::Mkxp => {
+                // Use system mkxp-z
+                Ok((Some(crate::mkxp::env_mkxp_path()), vec![]))
+            }
+            CompatType::Flash => {
+                // Use system Ruffle
+                Ok((Some(crate::flash::env_ruffle_path()), vec![]))
+            }
             CompatType::Love => {
                 // Use system LOVE2D runtime
 
-                Ok((Some("love".to_string()), vec![]))
+                Ok((Some(crate::love::env_love_path()), vec![]))
             }
         }
     }
@@ -200,13 +323,32 @@ pub fn default_game_configs() -> Vec<(u32, GameConfig)> {
 pub struct BosonConfig {
     pub default_compat_config: GameConfig,
     pub game_overrides: Vec<(u32, GameConfig)>,
+    /// The layers `default_compat_config` and `game_overrides` were merged from, with their sources
+    #[serde(skip)]
+    pub layers: ConfigLayers,
+}
+
+/// Config layers as they were loaded, in merge order
+#[derive(Default, Clone, Debug)]
+pub struct ConfigLayers {
+    pub defaults: Vec<(ConfigSource, GameConfig)>,
+    pub overrides: BTreeMap<u32, Vec<(ConfigSource, GameConfig)>>,
 }
 
 impl Default for BosonConfig {
     fn default() -> Self {
+        let game_overrides = default_game_configs();
+        let layers = ConfigLayers {
+            defaults: vec![(ConfigSource::Default, GameConfig::default())],
+            overrides: game_overrides
+                .iter()
+                .map(|(id, config)| (*id, vec![(ConfigSource::BuiltinGame, config.clone())]))
+                .collect(),
+        };
         Self {
             default_compat_config: GameConfig::default(),
-            game_overrides: default_game_configs(),
+            game_overrides,
+            layers,
         }
     }
 }
@@ -224,8 +366,14 @@ impl BosonConfig {
                     // Start with defaults and merge the loaded global default (override 0) on top,
                     // so any undefined keys in the loaded config will fall back to defaults.
                     let mut default_compat = GameConfig::default();
+                    let mut layers = ConfigLayers {
+                        defaults: vec![(ConfigSource::Default, GameConfig::default())],
+                        overrides: BTreeMap::new(),
+                    };
+                    let source = ConfigSource::GlobalConfig(cfg_path.clone());
                     if let Some(global_default) = cfg.defaults.take() {
                         Self::merge_config_static(&mut default_compat, &global_default);
+                        layers.defaults.push((source.clone(), global_default));
                     }
 
                     // For each game override, merge with defaults so missing fields use default settings
@@ -235,11 +383,19 @@ impl BosonConfig {
                         let mut merged = GameConfig::default();
                         Self::merge_config_static(&mut merged, &game_cfg);
                         merged_overrides.push((id, merged));
+                        layers.overrides.insert(
+                            id,
+                            vec![
+                                (ConfigSource::Default, GameConfig::default()),
+                                (source.clone(), game_cfg),
+                            ],
+                        );
                     }
 
                     BosonConfig {
                         default_compat_config: default_compat,
                         game_overrides: merged_overrides,
+                        layers,
                     }
                 }
                 Err(e) => {
@@ -259,6 +415,8 @@ impl BosonConfig {
 
         tracing::debug!("Loading configuration from paths: {:?}", config_paths);
 
+        let user_config_dir = path_search::user_config_dir();
+
         // Iterate through each config directory
         for config_dir in config_paths {
             if !config_dir.exists() {
@@ -273,7 +431,7 @@ impl BosonConfig {
                 .filter_map(|e| e.ok())
                 .filter(|entry| {
                     entry.file_type().is_file()
-                        && entry.path().extension().map_or(false, |ext| ext == "toml")
+                        && entry.path().extension().is_some_and(|ext| ext == "toml")
                 })
             {
                 let file_path = entry.path();
@@ -282,33 +440,13 @@ impl BosonConfig {
                 match Self::load_config_file(&file_path) {
                     Ok(game_config_file) => {
                         tracing::info!("Loaded config from: {:?}", file_path);
+                        let source = if user_config_dir.as_ref() == Some(&config_dir) {
+                            ConfigSource::UserConfig(file_path.clone())
+                        } else {
+                            ConfigSource::CompatToolData(file_path.clone())
+                        };
 
-                        // First, merge any defaults from this file into our global default config
-                        if let Some(file_defaults) = game_config_file.defaults {
-                            Self::merge_config_static(
-                                &mut config.default_compat_config,
-                                &file_defaults,
-                            );
-                        }
-
-                        // Then merge the loaded overrides into our config
-                        for (app_id, game_config) in game_config_file.overrides {
-                            // Check if we already have an override for this app_id
-                            let existing_index = config
-                                .game_overrides
-                                .iter()
-                                .position(|(id, _)| *id == app_id);
-
-                            if let Some(index) = existing_index {
-                                // Merge the configs, with the loaded one taking priority
-                                let mut existing_config = config.game_overrides[index].1.clone();
-                                Self::merge_config_static(&mut existing_config, &game_config);
-                                config.game_overrides[index].1 = existing_config;
-                            } else {
-                                // Add new override
-                                config.game_overrides.push((app_id, game_config));
-                            }
-                        }
+                        config.merge_file(game_config_file, source);
                     }
                     Err(e) => {
                         tracing::warn!("Failed to load config file {:?}: {}", file_path, e);
@@ -321,6 +459,37 @@ impl BosonConfig {
         Ok(config)
     }
 
+    /// Merge a config file on top of this config, recording it as a layer from `source`
+    fn merge_file(&mut self, file: GameConfigFile, source: ConfigSource) {
+        // First, merge any defaults from this file into our global default config
+        if let Some(file_defaults) = file.defaults {
+            Self::merge_config_static(&mut self.default_compat_config, &file_defaults);
+            self.layers.defaults.push((source.clone(), file_defaults));
+        }
+
+        // Then merge the loaded overrides into our config
+        for (app_id, game_config) in file.overrides {
+            self.layers
+                .overrides
+                .entry(app_id)
+                .or_default()
+                .push((source.clone(), game_config.clone()));
+
+            // Check if we already have an override for this app_id
+            let existing_index = self.game_overrides.iter().position(|(id, _)| *id == app_id);
+
+            if let Some(index) = existing_index {
+                // Merge the configs, with the loaded one taking priority
+                let mut existing_config = self.game_overrides[index].1.clone();
+                Self::merge_config_static(&mut existing_config, &game_config);
+                self.game_overrides[index].1 = existing_config;
+            } else {
+                // Add new override
+                self.game_overrides.push((app_id, game_config));
+            }
+        }
+    }
+
     /// Load a single config file
     fn load_config_file(path: &Path) -> Result<GameConfigFile> {
         let contents = fs::read_to_string(path)?;
@@ -368,6 +537,34 @@ impl BosonConfig {
         merged
     }
 
+    /// Get the game configuration for the given app ID like [`BosonConfig::get_game_config`],
+    /// along with where each of its fields comes from
+    pub fn get_game_config_provenance(&self, app_id: u32) -> Result<(GameConfig, Provenance)> {
+        let user_override = self.layers.overrides.get(&app_id);
+        let compat_type = self
+            .game_overrides
+            .iter()
+            .find(|(id, _)| *id == app_id)
+            .map(|(_, config)| &config.compat_type)
+            .unwrap_or(&self.default_compat_config.compat_type);
+
+        let layers: Vec<(ConfigSource, GameConfig)> = self
+            .layers
+            .defaults
+            .iter()
+            .chain(user_override.into_iter().flatten())
+            .cloned()
+            .collect();
+        provenance::trace_layers(
+            (
+                ConfigSource::RuntimeDefaults(compat_type.clone()),
+                compat_type.runtime_defaults(),
+            ),
+            &layers,
+            Self::merge_config_static,
+        )
+    }
+
     /// Helper function to merge one config into another
     /// Vec fields are extended, other fields are overwritten if present
     fn merge_config(&self, base: &mut GameConfig, overlay: &GameConfig) {
@@ -389,18 +586,63 @@ impl BosonConfig {
         if overlay.wrapper_command.is_some() {
             base.wrapper_command = overlay.wrapper_command.clone();
         }
-        
+
         if overlay.command_override.is_some() {
             base.command_override = overlay.command_override.clone();
         }
 
+        // Templates are replaced as a whole, merging them wouldn't make sense
+        if overlay.arg_template.is_some() {
+            base.arg_template = overlay.arg_template.clone();
+        }
+
         // Always extend wrapper_args additively - don't replace if base has args
         base.wrapper_args.extend(overlay.wrapper_args.clone());
 
         // Extend vec fields rather than replace
         base.env_vars.extend(overlay.env_vars.clone());
         base.append_args.extend(overlay.append_args.clone());
+        base.arg_map.extend(overlay.arg_map.clone());
         base.extra_preloads.extend(overlay.extra_preloads.clone());
+        base.godot_binaries.extend(overlay.godot_binaries.clone());
+        base.renpy_sdks.extend(overlay.renpy_sdks.clone());
+        base.java_homes.extend(overlay.java_homes.clone());
+        base.hashlink_modules
+            .extend(overlay.hashlink_modules.clone());
+        base.mkxp_rtps.extend(overlay.mkxp_rtps.clone());
+        base.love_binaries.extend(overlay.love_binaries.clone());
+        base.electron_paths.extend(overlay.electron_paths.clone());
+
+        if overlay.mkxp_soundfont.is_some() {
+            base.mkxp_soundfont = overlay.mkxp_soundfont.clone();
+        }
+
+        if overlay.ruffle_scale.is_some() {
+            base.ruffle_scale = overlay.ruffle_scale.clone();
+        }
+        if overlay.ruffle_quality.is_some() {
+            base.ruffle_quality = overlay.ruffle_quality.clone();
+        }
+        if overlay.ruffle_letterbox.is_some() {
+            base.ruffle_letterbox = overlay.ruffle_letterbox.clone();
+        }
+
+        if overlay.web_width.is_some() {
+            base.web_width = overlay.web_width;
+        }
+        if overlay.web_height.is_some() {
+            base.web_height = overlay.web_height;
+        }
+        if overlay.web_fullscreen.is_some() {
+            base.web_fullscreen = overlay.web_fullscreen;
+        }
+        if overlay.web_zoom.is_some() {
+            base.web_zoom = overlay.web_zoom;
+        }
+
+        if overlay.kill_grace_period.is_some() {
+            base.kill_grace_period = overlay.kill_grace_period;
+        }
 
         // Only override compat_tool_dir if overlay explicitly sets it to Some value
         // This preserves runtime defaults when user config doesn't specify compat_tool_dir
@@ -414,8 +656,10 @@ impl BosonConfig {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
+    use crate::provenance::FieldSource;
 
     #[test]
     fn test_runtime_defaults() {
@@ -425,6 +669,10 @@ mod tests {
         assert_eq!(electron_defaults.disable_steam_overlay, true);
         assert!(electron_defaults.env_vars.is_empty());
 
+        let nwjs_defaults = CompatType::NwJs.runtime_defaults();
+        assert_eq!(nwjs_defaults.compat_type, CompatType::NwJs);
+        assert!(nwjs_defaults.disable_steam_overlay);
+
         let love_defaults = CompatType::Love.runtime_defaults();
         assert_eq!(love_defaults.compat_type, CompatType::Love);
         assert_eq!(love_defaults.disable_steam_overlay, false);
@@ -488,6 +736,7 @@ mod tests {
                 ..Default::default()
             },
             game_overrides: vec![],
+            layers: ConfigLayers::default(),
         };
 
         // Add an Electron game with user overrides
@@ -556,6 +805,7 @@ mod tests {
                 ..Default::default()
             },
             game_overrides: vec![],
+            layers: ConfigLayers::default(),
         };
 
         // Add a DeferProton game with user overrides that don't specify compat_tool_dir
@@ -600,6 +850,7 @@ mod tests {
         let mut config = BosonConfig {
             default_compat_config: GameConfig::default(),
             game_overrides: vec![],
+            layers: ConfigLayers::default(),
         };
 
         // Add a DeferProton game with explicit compat_tool_dir override
@@ -621,6 +872,114 @@ mod tests {
             Some("Proton-GE-8-32".to_string())
         );
     }
+
+    #[test]
+    fn test_arg_template_and_arg_map_merging() {
+        let mut config = BosonConfig {
+            default_compat_config: GameConfig {
+                arg_template: Some(vec!["%exe%".to_string()]),
+                arg_map: [("-windowed".to_string(), vec!["--windowed".to_string()])].into(),
+                ..Default::default()
+            },
+            game_overrides: vec![],
+            layers: ConfigLayers::default(),
+        };
+
+        config.game_overrides.push((
+            888888,
+            GameConfig {
+                compat_type: CompatType::Love,
+                arg_template: Some(vec!["%exe%".to_string(), "%steam_args%".to_string()]),
+                arg_map: [("-novid".to_string(), vec![])].into(),
+                ..Default::default()
+            },
+        ));
+
+        let final_config = config.get_game_config(888888);
+
+        // Templates are replaced, not extended
+        assert_eq!(
+            final_config.arg_template,
+            Some(vec!["%exe%".to_string(), "%steam_args%".to_string()])
+        );
+        // Argument maps are merged
+        assert_eq!(final_config.arg_map.len(), 2);
+        assert_eq!(final_config.arg_map["-novid"], Vec::<String>::new());
+
+        // Games without a template keep the global one
+        let unknown_config = config.get_game_config(999999);
+        assert_eq!(unknown_config.arg_template, Some(vec!["%exe%".to_string()]));
+    }
+
+    #[test]
+    fn test_config_provenance() {
+        let user_file = PathBuf::from("/home/user/.config/boson.d/games.toml");
+        let data_file = PathBuf::from("/opt/boson/data/factory.toml");
+
+        let mut config = BosonConfig::default();
+        config.merge_file(
+            toml::from_str(
+                r#"
+                [override.1454400]
+                compat_type = "Electron"
+                env_vars = { FACTORY_VAR = "1" }
+                "#,
+            )
+            .unwrap(),
+            ConfigSource::CompatToolData(data_file.clone()),
+        );
+        config.merge_file(
+            toml::from_str(
+                r#"
+                [defaults]
+                wrapper_args = ["--common-arg"]
+
+                [override.1454400]
+                compat_type = "Electron"
+                env_vars = { USER_VAR = "1" }
+                electron_paths = ["~/electron/v8.5.5"]
+                "#,
+            )
+            .unwrap(),
+            ConfigSource::UserConfig(user_file.clone()),
+        );
+
+        let (merged, provenance) = config.get_game_config_provenance(1454400).unwrap();
+        // Replaying the layers gives the same config as merging them
+        assert_eq!(
+            serde_json::to_value(&merged).unwrap(),
+            serde_json::to_value(config.get_game_config(1454400)).unwrap()
+        );
+
+        assert_eq!(
+            provenance["env_vars"],
+            FieldSource::Entries(
+                [
+                    (
+                        "FACTORY_VAR".to_string(),
+                        ConfigSource::CompatToolData(data_file)
+                    ),
+                    (
+                        "USER_VAR".to_string(),
+                        ConfigSource::UserConfig(user_file.clone())
+                    ),
+                ]
+                .into()
+            )
+        );
+        assert_eq!(
+            provenance["wrapper_args"],
+            FieldSource::Elements(vec![ConfigSource::UserConfig(user_file.clone())])
+        );
+        assert_eq!(
+            provenance["electron_paths"],
+            FieldSource::Elements(vec![ConfigSource::UserConfig(user_file)])
+        );
+        assert_eq!(
+            provenance["compat_type"],
+            FieldSource::Value(ConfigSource::BuiltinGame)
+        );
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
@@ -640,6 +999,20 @@ pub struct GameConfig {
     /// Additional arguments to pass to the game executable
     pub append_args: Vec<String>,
 
+    /// Argument template for the launch command, controlling where the wrapper arguments,
+    /// Steam's arguments and the game target go. See [`crate::launch_args`] for the placeholders.
+    ///
+    /// If not set, defaults to `["%wrapper_args%", "%exe%", "%steam_args%", "%append_args%"]`.
+    /// Before templates, Steam's arguments went before the game target, use
+    /// `["%wrapper_args%", "%steam_args%", "%exe%", "%append_args%"]` to keep that order.
+    pub arg_template: Option<Vec<String>>,
+
+    /// Rewrite arguments passed by Steam into runtime arguments,
+    /// e.g. `arg_map = { "-windowed" = ["--windowed"] }`
+    ///
+    /// Mapping an argument to an empty list drops it
+    pub arg_map: BTreeMap<String, Vec<String>>,
+
     /// Additional libraries to preload via LD_PRELOAD
     pub extra_preloads: Vec<String>,
 
@@ -647,18 +1020,89 @@ pub struct GameConfig {
     /// May fix some compatibility issues
     pub disable_steam_overlay: bool,
 
+    /// Seconds the game's processes get to exit after Steam asks it to stop,
+    /// before any still running are killed. Defaults to 10
+    pub kill_grace_period: Option<u64>,
+
     /// Specify the directory of the compatibility tool to use
     /// This is for deferring to another compatibility tool like Proton
     ///
     /// If not set, this will default to "Proton - Experimental"
     ///
     /// This looks up the compatibility tool in Steam's compatibilitytools.d directory
-    /// or the Steam library paths
+    /// or the Steam library paths, by directory name, display name (e.g. "GE-Proton9-20"),
+    /// internal name (e.g. "proton_experimental") or app ID (e.g. "1493710")
     #[serde(default = "default_compat_tool_dir")]
     pub compat_tool_dir: Option<String>,
 
     /// Override the executable command used to launch the game
     pub command_override: Option<String>,
+
+    /// Host Godot binaries, keyed by engine version prefix
+    /// e.g. `godot_binaries = { "3" = "/usr/bin/godot3", "4.2" = "~/godot/Godot_v4.2-stable" }`
+    ///
+    /// The most specific version matching the game's data pack is used
+    pub godot_binaries: BTreeMap<String, String>,
+
+    /// Host Electron installs for Electron games, either the `electron` binary or its directory
+    ///
+    /// The install closest to the game's bundled Electron version is picked,
+    /// from the `version` file Electron distributions ship
+    pub electron_paths: Vec<String>,
+
+    /// Host LOVE binaries by version, for LOVE games
+    ///
+    /// e.g. `love_binaries = { "11" = "/usr/bin/love", "0.10" = "~/love-0.10.2/love" }`
+    ///
+    /// The most specific version matching the game's `conf.lua` or `love.dll` is used
+    pub love_binaries: BTreeMap<String, String>,
+
+    /// Paths to host Ren'Py SDKs (directories containing `renpy.sh`)
+    ///
+    /// The SDK matching the game's Ren'Py version is picked automatically
+    pub renpy_sdks: Vec<String>,
+
+    /// Paths to host JREs/JDKs (`JAVA_HOME`-style directories), `JAVA_HOME` is always considered
+    ///
+    /// The one matching the Java version the game was shipped with is picked automatically
+    pub java_homes: Vec<String>,
+
+    /// Linux builds of HashLink native modules, keyed by module name
+    /// e.g. `hashlink_modules = { steam = "~/hashlink/steam.hdll" }`
+    ///
+    /// Modules not listed here are taken from Boson's `lib/hashlink` directory,
+    /// map a module to an empty string to not supply it at all
+    pub hashlink_modules: BTreeMap<String, String>,
+
+    /// RPG Maker RTP install directories for mkxp-z, keyed by the RTP name in `Game.ini`
+    /// e.g. `mkxp_rtps = { RPGVXAce = "~/rtp/RPGVXAce", Standard = "~/rtp/Standard" }`
+    pub mkxp_rtps: BTreeMap<String, String>,
+
+    /// Soundfont used by mkxp-z for MIDI playback
+    ///
+    /// If not set, a system General MIDI soundfont is used if one can be found
+    pub mkxp_soundfont: Option<String>,
+
+    /// Ruffle scale mode for Flash games, e.g. `show-all`, `no-border`, `exact-fit` or `no-scale`
+    pub ruffle_scale: Option<String>,
+
+    /// Ruffle render quality for Flash games, e.g. `low`, `medium`, `high` or `best`
+    pub ruffle_quality: Option<String>,
+
+    /// Ruffle letterbox mode for Flash games, `off`, `fullscreen` or `on`
+    pub ruffle_letterbox: Option<String>,
+
+    /// Initial window width for web games, defaults to 1280
+    pub web_width: Option<u32>,
+
+    /// Initial window height for web games, defaults to 720
+    pub web_height: Option<u32>,
+
+    /// Start web games in fullscreen
+    pub web_fullscreen: Option<bool>,
+
+    /// Page zoom factor for web games, e.g. `1.5`
+    pub web_zoom: Option<f64>,
 }
 
 fn default_compat_tool_dir() -> Option<String> {
@@ -696,6 +1140,7 @@ impl GameConfigFile {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod game_config_file_tests {
     use super::*;
     #[test]
@@ -0,0 +1,330 @@
+//! Electron version detection and runtime selection
+//!
+//! Electron apps are only expected to run on the Electron major version they were built with,
+//! so we work out which version the game shipped with and pick the closest host Electron.
+use std::path::{Path, PathBuf};
+
+use crate::{asar, path_search, pe};
+
+/// An Electron version, e.g. `8.5.5`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ElectronVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl ElectronVersion {
+    /// Parse a version, tolerating a `v` prefix, npm range operators and prerelease suffixes
+    /// (`v8.5.5`, `^13.1.0`, `~30.0.0-beta.2`)
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version
+            .trim()
+            .trim_start_matches(['v', '^', '~', '=', '>', '<', ' ']);
+        let version = version.split(['-', '+', ' ']).next()?;
+        let mut parts = version.split('.').map(|p| p.parse::<u32>());
+        Some(Self {
+            major: parts.next()?.ok()?,
+            minor: parts.next().unwrap_or(Ok(0)).ok()?,
+            patch: parts.next().unwrap_or(Ok(0)).ok()?,
+        })
+    }
+}
+
+impl std::fmt::Display for ElectronVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Where the Electron version of a game was detected from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionSource {
+    /// The `version` file Electron distributions ship next to the executable
+    VersionFile,
+    /// The version resource of the Windows executable
+    Executable,
+    /// `devDependencies` (or `dependencies`) in the app's `package.json`
+    PackageJson,
+}
+
+impl std::fmt::Display for VersionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionSource::VersionFile => write!(f, "version file"),
+            VersionSource::Executable => write!(f, "executable version resource"),
+            VersionSource::PackageJson => write!(f, "package.json"),
+        }
+    }
+}
+
+/// Read an Electron distribution's `version` file
+pub fn read_version_file(dir: &Path) -> Option<ElectronVersion> {
+    ElectronVersion::parse(&std::fs::read_to_string(dir.join("version")).ok()?)
+}
+
+/// Read the `electron` dependency from a `package.json`
+pub fn read_package_json_version(package_json: &str) -> Option<ElectronVersion> {
+    let package: serde_json::Value = serde_json::from_str(package_json).ok()?;
+    ["devDependencies", "dependencies"]
+        .iter()
+        .find_map(|key| package.get(key)?.get("electron")?.as_str())
+        .and_then(ElectronVersion::parse)
+}
+
+/// Whether the executable's version resource still describes Electron itself
+///
+/// Games often rebrand it with their own product name and version, which says nothing
+/// about the Electron version.
+fn is_electron_branded(game_exec_path: &Path) -> bool {
+    ["ProductName", "OriginalFilename"].iter().any(|key| {
+        pe::version_string(game_exec_path, key)
+            .is_some_and(|value| value.to_lowercase().starts_with("electron"))
+    })
+}
+
+/// Read the Electron version from the executable's version resource
+fn read_executable_version(game_exec_path: &Path) -> Option<ElectronVersion> {
+    let [major, minor, patch, _] = pe::file_version(game_exec_path)?;
+    (major > 0).then_some(ElectronVersion {
+        major,
+        minor,
+        patch,
+    })
+}
+
+/// Detect the Electron version the game shipped with
+///
+/// In order of preference:
+/// - The `version` file next to the executable
+/// - The executable's version resource, if it's still branded as Electron
+/// - The `electron` dependency in the app's `package.json`
+///
+/// A rebranded version resource holds the game's own version, so it's never used.
+pub fn detect_version(
+    game_exec_path: &Path,
+    app_path: &Path,
+) -> Option<(ElectronVersion, VersionSource)> {
+    if let Some(version) = game_exec_path.parent().and_then(read_version_file) {
+        return Some((version, VersionSource::VersionFile));
+    }
+
+    if is_electron_branded(game_exec_path) {
+        if let Some(version) = read_executable_version(game_exec_path) {
+            return Some((version, VersionSource::Executable));
+        }
+    }
+
+    let package_json = if app_path.is_dir() {
+        std::fs::read(app_path.join("package.json")).ok()
+    } else {
+        asar::Archive::open(app_path)
+            .and_then(|archive| archive.read_file("package.json"))
+            .ok()
+    };
+    package_json
+        .and_then(|p| read_package_json_version(&String::from_utf8_lossy(&p)))
+        .map(|version| (version, VersionSource::PackageJson))
+}
+
+/// Find the version of a host Electron binary, from the `version` file next to it
+fn installed_version(binary: &Path) -> Option<ElectronVersion> {
+    let binary = binary.canonicalize().ok()?;
+    read_version_file(binary.parent()?)
+}
+
+/// Resolve a configured Electron path, which may be the binary or its directory
+fn electron_binary(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join("electron")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Electron installs from the environment, `ELECTRON_PATH` and `electron` from `$PATH`
+pub fn host_electron_candidates() -> Vec<PathBuf> {
+    std::env::var_os("ELECTRON_PATH")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(path_search::find_in_path("electron"))
+        .collect()
+}
+
+/// Pick the host Electron closest to the game's version
+///
+/// Candidates are the given `electron_paths`, binaries or directories containing them,
+/// e.g. the configured paths, Boson-managed installs and [`host_electron_candidates`].
+/// The same major version is preferred (the closest minor/patch within it), then the closest
+/// newer major, then the newest older major. Anything other than the same major is warned about.
+pub fn select_electron(required: Option<ElectronVersion>, electron_paths: &[PathBuf]) -> String {
+    let mut candidates: Vec<(PathBuf, ElectronVersion)> = electron_paths
+        .iter()
+        .map(|p| electron_binary(p))
+        .filter_map(|binary| {
+            let version = installed_version(&binary);
+            if version.is_none() {
+                tracing::debug!(?binary, "Could not find the version of Electron install");
+            }
+            version.map(|v| (binary, v))
+        })
+        .collect();
+    candidates.sort_by_key(|(_, v)| *v);
+    candidates.dedup_by(|a, b| a.0 == b.0);
+    tracing::debug!(?candidates, "Available host Electron installs");
+
+    let Some(required) = required else {
+        tracing::warn!("Could not detect the Electron version the game was shipped with");
+        return candidates
+            .last()
+            .map(|(binary, _)| binary.display().to_string())
+            .unwrap_or_else(path_search::env_electron_path);
+    };
+
+    let same_major = candidates
+        .iter()
+        .filter(|(_, v)| v.major == required.major)
+        .min_by_key(|(_, v)| {
+            let distance = |a: u32, b: u32| a.abs_diff(b);
+            (
+                distance(v.minor, required.minor),
+                distance(v.patch, required.patch),
+            )
+        });
+    if let Some((binary, version)) = same_major {
+        tracing::info!(?binary, %version, "Using host Electron with matching major version");
+        return binary.display().to_string();
+    }
+
+    let fallback = candidates
+        .iter()
+        .find(|(_, v)| v.major > required.major)
+        .or_else(|| candidates.last());
+    match fallback {
+        Some((binary, version)) => {
+            tracing::warn!(
+                ?binary,
+                "No Electron {} install found, using Electron {} instead. \
+                 The game may not work, run `boson runtime install electron {}` \
+                 or add an Electron {} install to electron_paths",
+                required.major,
+                version,
+                required,
+                required.major
+            );
+            binary.display().to_string()
+        }
+        None => {
+            let electron = path_search::env_electron_path();
+            tracing::warn!(
+                "No Electron installs with a known version found, using {:?} for Electron {}. \
+                 Run `boson runtime install electron {}` to install a matching one",
+                electron,
+                required,
+                required
+            );
+            electron
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_versions() {
+        assert_eq!(
+            ElectronVersion::parse("v8.5.5\n"),
+            Some(ElectronVersion {
+                major: 8,
+                minor: 5,
+                patch: 5
+            })
+        );
+        assert_eq!(
+            ElectronVersion::parse("~30.0.0-beta.2").map(|v| v.to_string()),
+            Some("30.0.0".to_string())
+        );
+        assert_eq!(
+            read_package_json_version(r#"{"devDependencies": {"electron": "^13.1.0"}}"#)
+                .map(|v| v.major),
+            Some(13)
+        );
+        assert_eq!(read_package_json_version(r#"{"name": "game"}"#), None);
+    }
+
+    #[test]
+    fn test_select_electron() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let installs: Vec<PathBuf> = ["8.5.5", "13.6.9", "30.0.1"]
+            .iter()
+            .map(|version| {
+                let dir = temp_dir.path().join(version);
+                std::fs::create_dir_all(&dir).unwrap();
+                std::fs::write(dir.join("electron"), "").unwrap();
+                std::fs::write(dir.join("version"), version).unwrap();
+                dir
+            })
+            .collect();
+
+        let electron = |version: &str| {
+            temp_dir
+                .path()
+                .join(version)
+                .join("electron")
+                .display()
+                .to_string()
+        };
+        let select = |version: &str| select_electron(ElectronVersion::parse(version), &installs);
+        assert_eq!(select("8.2.0"), electron("8.5.5"));
+        assert_eq!(select("11.0.0"), electron("13.6.9"));
+        assert_eq!(select("31.0.0"), electron("30.0.1"));
+    }
+
+    #[test]
+    fn test_detect_rebranded_version() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let app = temp_dir.path().join("resources").join("app");
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::write(
+            app.join("package.json"),
+            r#"{"devDependencies": {"electron": "^13.1.0"}}"#,
+        )
+        .unwrap();
+        let exe = temp_dir.path().join("Game.exe");
+        let version_resource = |product_name: &str, version: u32| {
+            let mut bytes = b"MZ".to_vec();
+            bytes.extend(pe::FIXED_FILE_INFO_SIGNATURE.to_le_bytes());
+            bytes.extend(0x0001_0000u32.to_le_bytes());
+            bytes.extend((version << 16).to_le_bytes());
+            bytes.extend(0u32.to_le_bytes());
+            bytes.extend(pe::tests::version_string_entry("ProductName", product_name));
+            bytes
+        };
+
+        // The game's own version, 2.0.0, must not be taken for Electron 2
+        std::fs::write(&exe, version_resource("Some Game", 2)).unwrap();
+        assert_eq!(
+            detect_version(&exe, &app),
+            Some((
+                ElectronVersion::parse("13.1.0").unwrap(),
+                VersionSource::PackageJson
+            ))
+        );
+
+        std::fs::write(&exe, version_resource("Electron", 12)).unwrap();
+        assert_eq!(
+            detect_version(&exe, &app),
+            Some((
+                ElectronVersion::parse("12.0.0").unwrap(),
+                VersionSource::Executable
+            ))
+        );
+
+        // Without an electron dependency, the game's version is still not Electron's
+        std::fs::write(app.join("package.json"), r#"{"name": "game"}"#).unwrap();
+        std::fs::write(&exe, version_resource("Some Game", 2)).unwrap();
+        assert_eq!(detect_version(&exe, &app), None);
+    }
+}
@@ -0,0 +1,176 @@
+//! Flash projector support via Ruffle
+//!
+//! A Flash standalone projector game is the Windows projector with the SWF appended to it,
+//! followed by an 8 byte footer: the magic `0xFA123456` and the SWF length (both u32 LE).
+//! We extract the SWF and run it with the host Ruffle desktop player.
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use stable_eyre::Result;
+
+/// Projector footer magic, right before the SWF length
+const PROJECTOR_MAGIC: u32 = 0xFA12_3456;
+
+pub fn env_ruffle_path() -> String {
+    std::env::var("RUFFLE_PATH").unwrap_or_else(|_| "ruffle".to_string())
+}
+
+fn is_swf_signature(bytes: &[u8]) -> bool {
+    matches!(bytes, [b'F' | b'C' | b'Z', b'W', b'S', ..])
+}
+
+/// Find the SWF embedded in a projector executable
+///
+/// Returns the SWF offset and length, or `None` if the file isn't a projector.
+pub fn find_embedded_swf(exe: &Path) -> Result<Option<(u64, u64)>> {
+    let mut file = File::open(exe)?;
+    let len = file.metadata()?.len();
+    if len < 8 {
+        return Ok(None);
+    }
+
+    let mut footer = [0u8; 8];
+    file.seek(SeekFrom::End(-8))?;
+    file.read_exact(&mut footer)?;
+    let magic = u32::from_le_bytes(footer[0..4].try_into()?);
+    let swf_len = u32::from_le_bytes(footer[4..8].try_into()?) as u64;
+    if magic != PROJECTOR_MAGIC {
+        return Ok(None);
+    }
+
+    let Some(offset) = len.checked_sub(8).and_then(|l| l.checked_sub(swf_len)) else {
+        tracing::warn!(
+            ?exe,
+            swf_len,
+            "Projector footer SWF length is larger than the file"
+        );
+        return Ok(None);
+    };
+
+    let mut signature = [0u8; 3];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut signature)?;
+    if !is_swf_signature(&signature) {
+        tracing::warn!(?exe, offset, "Projector footer doesn't point to a SWF");
+        return Ok(None);
+    }
+    Ok(Some((offset, swf_len)))
+}
+
+/// Where the SWF of a projector is extracted to in the cache directory
+pub fn swf_path(exe: &Path, cache_dir: &Path) -> PathBuf {
+    let name = exe
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "game".to_string());
+    cache_dir.join(format!("{name}.swf"))
+}
+
+/// Extract the SWF from a projector into the cache directory, and return its path
+///
+/// The cached SWF is reused as long as it's newer than the executable and has the right size,
+/// so game updates get picked up automatically.
+pub fn extract_swf(exe: &Path, offset: u64, swf_len: u64, cache_dir: &Path) -> Result<PathBuf> {
+    let swf_path = swf_path(exe, cache_dir);
+
+    let is_fresh = |cached: &Path| -> Option<bool> {
+        let cached = cached.metadata().ok()?;
+        let exe = exe.metadata().ok()?;
+        Some(cached.len() == swf_len && cached.modified().ok()? >= exe.modified().ok()?)
+    };
+    if is_fresh(&swf_path).unwrap_or(false) {
+        tracing::debug!(?swf_path, "Using cached SWF");
+        return Ok(swf_path);
+    }
+
+    tracing::info!(?exe, ?swf_path, "Extracting SWF from Flash projector");
+    std::fs::create_dir_all(cache_dir)?;
+    let mut file = File::open(exe)?;
+    file.seek(SeekFrom::Start(offset))?;
+    // Extract to a temporary name first, so an interrupted or concurrent extraction
+    // is never picked up as cached
+    let partial = swf_path.with_extension(format!("swf.{}.partial", std::process::id()));
+    let copied = std::io::copy(&mut file.take(swf_len), &mut File::create(&partial)?)?;
+    if copied != swf_len {
+        std::fs::remove_file(&partial)?;
+        return Err(stable_eyre::eyre::eyre!(
+            "{:?} ends before the end of its SWF, expected {} bytes but got {}",
+            exe,
+            swf_len,
+            copied
+        ));
+    }
+    std::fs::rename(&partial, &swf_path)?;
+    Ok(swf_path)
+}
+
+/// Find a loose SWF in the game directory, for games that ship one next to the projector
+pub fn find_loose_swf(game_dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(game_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("swf"))
+        })
+        .max_by_key(|p| p.metadata().map(|m| m.len()).unwrap_or_default())
+}
+
+/// Ruffle command line options from the game config
+pub fn ruffle_args(
+    scale: Option<&str>,
+    quality: Option<&str>,
+    letterbox: Option<&str>,
+) -> Vec<String> {
+    [
+        ("--scale", scale),
+        ("--quality", quality),
+        ("--letterbox", letterbox),
+    ]
+    .iter()
+    .filter_map(|(flag, value)| value.map(|v| format!("{flag}={v}")))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_embedded_swf() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let exe = temp_dir.path().join("Game.exe");
+        let swf = b"FWS\x0a fake swf contents";
+
+        let mut bytes = b"MZ fake flash projector".to_vec();
+        bytes.extend(swf);
+        bytes.extend(PROJECTOR_MAGIC.to_le_bytes());
+        bytes.extend((swf.len() as u32).to_le_bytes());
+        std::fs::write(&exe, bytes).unwrap();
+
+        let (offset, len) = find_embedded_swf(&exe).unwrap().unwrap();
+        assert_eq!(len, swf.len() as u64);
+
+        let cache_dir = temp_dir.path().join("cache");
+        let extracted = extract_swf(&exe, offset, len, &cache_dir).unwrap();
+        assert_eq!(extracted, cache_dir.join("Game.swf"));
+        assert_eq!(std::fs::read(&extracted).unwrap(), swf);
+        assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+        let other = temp_dir.path().join("Other.exe");
+        std::fs::write(&other, b"MZ not a projector").unwrap();
+        assert!(find_embedded_swf(&other).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_ruffle_args() {
+        assert_eq!(
+            ruffle_args(Some("show-all"), None, Some("on")),
+            vec!["--scale=show-all".to_string(), "--letterbox=on".to_string()]
+        );
+    }
+}
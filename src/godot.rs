@@ -0,0 +1,249 @@
+//! Godot engine support
+//!
+//! Windows Godot exports are a stock export template plus a `.pck` data pack, either as a
+//! separate file or appended to the executable. The pack is engine-agnostic, so we can
+//! just run it with a host Godot binary of the same version.
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use stable_eyre::Result;
+
+use crate::path_search;
+
+/// "GDPC" in little-endian
+const PCK_MAGIC: u32 = 0x4350_4447;
+
+/// Header of a Godot PCK data pack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PckHeader {
+    pub format_version: u32,
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl std::fmt::Display for PckHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+fn read_u32(file: &mut File) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    file.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(file: &mut File) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    file.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Read the PCK header from a standalone pack, or a pack embedded in an executable
+///
+/// Embedded packs are found the same way Godot does it: the executable ends with
+/// the pack size (u64) followed by the magic, and the pack sits right before that.
+///
+/// Returns `None` if the file doesn't contain a pack.
+pub fn read_pck_header(path: &Path) -> Result<Option<PckHeader>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len < 4 {
+        return Ok(None);
+    }
+
+    let pack_offset = if read_u32(&mut file)? == PCK_MAGIC {
+        0
+    } else {
+        if len < 12 {
+            return Ok(None);
+        }
+        file.seek(SeekFrom::End(-4))?;
+        if read_u32(&mut file)? != PCK_MAGIC {
+            return Ok(None);
+        }
+        file.seek(SeekFrom::End(-12))?;
+        let pack_size = read_u64(&mut file)?;
+        let Some(offset) = len.checked_sub(pack_size).and_then(|o| o.checked_sub(12)) else {
+            tracing::warn!(
+                ?path,
+                pack_size,
+                "Embedded PCK size is larger than the file"
+            );
+            return Ok(None);
+        };
+        file.seek(SeekFrom::Start(offset))?;
+        if read_u32(&mut file)? != PCK_MAGIC {
+            tracing::warn!(
+                ?path,
+                offset,
+                "Embedded PCK trailer points to an invalid pack"
+            );
+            return Ok(None);
+        }
+        offset
+    };
+    tracing::trace!(?path, pack_offset, "Found PCK magic");
+
+    Ok(Some(PckHeader {
+        format_version: read_u32(&mut file)?,
+        major: read_u32(&mut file)?,
+        minor: read_u32(&mut file)?,
+        patch: read_u32(&mut file)?,
+    }))
+}
+
+/// Find the game's data pack and read its header
+///
+/// Looks in the same order as Godot itself:
+/// - A pack embedded in the executable
+/// - `<executable name>.pck` next to the executable
+/// - Any other `.pck` file next to the executable
+pub fn find_pack(game_exec_path: &Path) -> Option<(PathBuf, PckHeader)> {
+    let game_dir = game_exec_path.parent()?;
+
+    let mut candidates = vec![
+        game_exec_path.to_path_buf(),
+        game_exec_path.with_extension("pck"),
+    ];
+    if let Ok(entries) = std::fs::read_dir(game_dir) {
+        let mut others: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("pck"))
+            })
+            .collect();
+        others.sort();
+        candidates.extend(others);
+    }
+
+    candidates
+        .into_iter()
+        .filter(|p| p.is_file())
+        .find_map(|p| match read_pck_header(&p) {
+            Ok(Some(header)) => {
+                tracing::info!(path = ?p, version = %header, "Found Godot data pack");
+                Some((p, header))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!(path = ?p, "Failed to read PCK header: {:?}", e);
+                None
+            }
+        })
+}
+
+pub fn env_godot_path() -> Option<String> {
+    std::env::var("GODOT_PATH").ok()
+}
+
+/// Pick a host Godot binary matching the pack's engine version
+///
+/// `godot_binaries` maps version prefixes (e.g. `"3"`, `"3.5"`, `"4.2.1"`) to binaries,
+/// the most specific matching prefix wins. If nothing matches, fall back to `GODOT_PATH`,
+/// then `godot<major>` if it's in `$PATH`, then plain `godot`.
+pub fn select_binary(header: &PckHeader, godot_binaries: &BTreeMap<String, String>) -> String {
+    let candidates = [
+        header.to_string(),
+        format!("{}.{}", header.major, header.minor),
+        header.major.to_string(),
+    ];
+    for version in candidates.iter() {
+        if let Some(binary) = godot_binaries.get(version) {
+            tracing::info!(%version, ?binary, "Using configured Godot binary");
+            return binary.clone();
+        }
+    }
+
+    if let Some(binary) = env_godot_path() {
+        tracing::info!(?binary, "Using Godot binary from GODOT_PATH");
+        return binary;
+    }
+
+    let versioned = format!("godot{}", header.major);
+    if path_search::find_in_path(&versioned).is_some() {
+        tracing::info!(binary = ?versioned, "Using versioned Godot binary from PATH");
+        return versioned;
+    }
+
+    tracing::warn!(
+        version = %header,
+        "No Godot binary configured for this engine version, falling back to `godot`"
+    );
+    "godot".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pck_bytes(version: (u32, u32, u32)) -> Vec<u8> {
+        let mut bytes = vec![];
+        for field in [PCK_MAGIC, 1, version.0, version.1, version.2] {
+            bytes.extend(field.to_le_bytes());
+        }
+        // reserved space and an empty file table
+        bytes.extend([0u8; 68]);
+        bytes
+    }
+
+    #[test]
+    fn test_standalone_pck() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let pck = temp_dir.path().join("game.pck");
+        std::fs::write(&pck, pck_bytes((3, 5, 2))).unwrap();
+
+        let header = read_pck_header(&pck).unwrap().unwrap();
+        assert_eq!(header.to_string(), "3.5.2");
+        assert_eq!(header.format_version, 1);
+    }
+
+    #[test]
+    fn test_embedded_pck() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let exe = temp_dir.path().join("game.exe");
+        let pck = pck_bytes((4, 2, 1));
+
+        let mut bytes = b"MZ fake export template".to_vec();
+        bytes.extend(&pck);
+        bytes.extend((pck.len() as u64).to_le_bytes());
+        bytes.extend(PCK_MAGIC.to_le_bytes());
+        std::fs::write(&exe, bytes).unwrap();
+
+        let (path, header) = find_pack(&exe).unwrap();
+        assert_eq!(path, exe);
+        assert_eq!(header.to_string(), "4.2.1");
+
+        // Not a pack at all
+        let other = temp_dir.path().join("other.exe");
+        std::fs::write(&other, b"MZ not a godot game").unwrap();
+        assert!(read_pck_header(&other).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_select_binary() {
+        let header = PckHeader {
+            format_version: 1,
+            major: 3,
+            minor: 5,
+            patch: 2,
+        };
+        let binaries: BTreeMap<String, String> = [
+            ("3".to_string(), "/opt/godot3/godot".to_string()),
+            ("3.5".to_string(), "/opt/godot35/godot".to_string()),
+            ("4".to_string(), "/opt/godot4/godot".to_string()),
+        ]
+        .into();
+        assert_eq!(select_binary(&header, &binaries), "/opt/godot35/godot");
+
+        let header = PckHeader { minor: 6, ..header };
+        assert_eq!(select_binary(&header, &binaries), "/opt/godot3/godot");
+    }
+}
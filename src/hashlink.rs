@@ -0,0 +1,139 @@
+//! HashLink support
+//!
+//! Haxe games targeting HashLink ship their bytecode (`hlboot.dat` or a `.hl` file) with a
+//! Windows `hl.exe` and Windows builds of the `.hdll` native modules. The bytecode is portable,
+//! so we run it with a host `hl` and Linux builds of the native modules.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use stable_eyre::Result;
+
+pub fn env_hashlink_path() -> String {
+    std::env::var("HASHLINK_PATH").unwrap_or_else(|_| "hl".to_string())
+}
+
+/// Find the game's bytecode, `hlboot.dat` first as that's what `hl.exe` loads by default
+pub fn find_bytecode(game_dir: &Path) -> Option<PathBuf> {
+    let hlboot = game_dir.join("hlboot.dat");
+    if hlboot.is_file() {
+        return Some(hlboot);
+    }
+
+    let mut bytecode: Vec<PathBuf> = std::fs::read_dir(game_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "hl"))
+        .collect();
+    bytecode.sort();
+    bytecode.into_iter().next()
+}
+
+/// List the native modules the game ships, by name (e.g. `fmt` for `fmt.hdll`)
+pub fn game_modules(game_dir: &Path) -> Vec<String> {
+    let mut modules: Vec<String> = std::fs::read_dir(game_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "hdll"))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .collect();
+    modules.sort();
+    modules
+}
+
+/// Resolve the Linux build of each module the game needs
+///
+/// `module_map` (from `hashlink_modules`) takes priority, mapping a module name to a `.hdll` path,
+/// or to an empty string to skip it. Otherwise Boson's `lib/hashlink/<name>.hdll` is used.
+pub fn resolve_modules(
+    modules: &[String],
+    module_map: &BTreeMap<String, String>,
+    boson_modules_dir: &Path,
+) -> BTreeMap<String, PathBuf> {
+    let mut resolved = BTreeMap::new();
+    for module in modules.iter().chain(module_map.keys()) {
+        if resolved.contains_key(module) {
+            continue;
+        }
+        let path = match module_map.get(module) {
+            Some(path) if path.is_empty() => {
+                tracing::debug!(module, "Module disabled by config");
+                continue;
+            }
+            Some(path) => PathBuf::from(path),
+            None => boson_modules_dir.join(format!("{module}.hdll")),
+        };
+        if path.is_file() {
+            tracing::debug!(module, ?path, "Using native module");
+            resolved.insert(module.clone(), path);
+        } else {
+            tracing::warn!(
+                module,
+                ?path,
+                "No Linux build of native module found, the game may fail to start"
+            );
+        }
+    }
+    resolved
+}
+
+/// Link the resolved modules into a directory under the compat data directory,
+/// which gets added to `LD_LIBRARY_PATH` so `hl` finds them by name
+pub fn link_modules(modules: &BTreeMap<String, PathBuf>, dir: &Path) -> Result<()> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    std::fs::create_dir_all(dir)?;
+
+    for (module, path) in modules {
+        std::os::unix::fs::symlink(path, dir.join(format!("{module}.hdll")))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_bytecode_and_modules() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let game_dir = temp_dir.path().join("game");
+        std::fs::create_dir(&game_dir).unwrap();
+        for file in ["game.hl", "fmt.hdll", "sdl.hdll", "steam.hdll", "hl.exe"] {
+            std::fs::write(game_dir.join(file), b"").unwrap();
+        }
+        assert_eq!(find_bytecode(&game_dir), Some(game_dir.join("game.hl")));
+        std::fs::write(game_dir.join("hlboot.dat"), b"").unwrap();
+        assert_eq!(find_bytecode(&game_dir), Some(game_dir.join("hlboot.dat")));
+
+        let modules = game_modules(&game_dir);
+        assert_eq!(modules, vec!["fmt", "sdl", "steam"]);
+
+        let boson_dir = temp_dir.path().join("lib/hashlink");
+        std::fs::create_dir_all(&boson_dir).unwrap();
+        std::fs::write(boson_dir.join("fmt.hdll"), b"").unwrap();
+        std::fs::write(boson_dir.join("sdl.hdll"), b"").unwrap();
+        let custom_ui = temp_dir.path().join("custom_ui.hdll");
+        std::fs::write(&custom_ui, b"").unwrap();
+
+        let module_map: BTreeMap<String, String> = [
+            ("sdl".to_string(), String::new()),
+            ("ui".to_string(), custom_ui.display().to_string()),
+        ]
+        .into();
+        let resolved = resolve_modules(&modules, &module_map, &boson_dir);
+        assert_eq!(
+            resolved,
+            [
+                ("fmt".to_string(), boson_dir.join("fmt.hdll")),
+                ("ui".to_string(), custom_ui),
+            ]
+            .into()
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! Java support
+//!
+//! Java games (e.g. libGDX/LWJGL games like Slay the Spire) usually ship a jar, a launcher
+//! executable and a bundled Windows JRE. Only the JRE keeps them from running natively,
+//! so we run the jar with a host JRE of the same major version instead.
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use stable_eyre::Result;
+
+use crate::pe;
+
+/// Directories games usually bundle their JRE in
+const BUNDLED_JRE_DIRS: [&str; 5] = ["jre", "jre64", "runtime", "java", "jdk"];
+
+/// Launcher config written by packr, which most libGDX games use
+/// (`config.json` next to the executable)
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PackrConfig {
+    pub jre_path: Option<String>,
+    /// Older packr versions only have a single jar
+    pub jar: Option<String>,
+    pub class_path: Vec<String>,
+    pub main_class: Option<String>,
+    pub vm_args: Vec<String>,
+}
+
+/// How to launch a detected Java game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaGame {
+    /// Jars making up the class path, the first one being the main jar
+    pub class_path: Vec<PathBuf>,
+    /// Main class, if not set the main jar is run with `-jar`
+    pub main_class: Option<String>,
+    /// JVM options from the game's launcher config
+    pub vm_args: Vec<String>,
+    /// Major Java version of the bundled JRE, if any
+    pub java_version: Option<u32>,
+}
+
+/// Parse a major Java version, e.g. `1.8.0_252` is 8, `17.0.2` is 17
+pub fn parse_java_version(version: &str) -> Option<u32> {
+    let version = version.trim().trim_matches('"');
+    let mut parts = version.split(['.', '_', '-', '+']);
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+/// Read the major Java version of a JRE/JDK from its `release` file
+pub fn read_jre_version(java_home: &Path) -> Option<u32> {
+    let release = std::fs::read_to_string(java_home.join("release")).ok()?;
+    release.lines().find_map(|line| {
+        let value = line.strip_prefix("JAVA_VERSION=")?;
+        parse_java_version(value)
+    })
+}
+
+/// Read JVM options from a Launch4j `<exe>.l4j.ini` file
+fn read_l4j_ini(game_exec_path: &Path) -> Vec<String> {
+    let ini = game_exec_path.with_extension("l4j.ini");
+    std::fs::read_to_string(ini)
+        .map(|contents| {
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Check if the file is (or ends with) a zip archive, like Launch4j executables wrapping their jar
+fn is_zip(path: &Path) -> bool {
+    std::fs::File::open(path)
+        .ok()
+        .and_then(|f| zip::ZipArchive::new(f).ok())
+        .is_some()
+}
+
+/// Find jar names referenced by the executable itself, e.g. the Launch4j embedded config
+fn jars_from_exe(game_exec_path: &Path, game_dir: &Path) -> Vec<PathBuf> {
+    // Long enough for any path Windows would take
+    const MAX_NAME_LEN: usize = 260;
+    let is_text = |b: &u8| b.is_ascii_graphic() || *b == b' ';
+
+    pe::scan_all(game_exec_path, b".jar", MAX_NAME_LEN, 1)
+        .iter()
+        .filter_map(|window| {
+            // The name has to end right after `.jar`, at a non-text byte or the end of the file
+            let end = window.len() - usize::from(!window.ends_with(b".jar"));
+            if window.get(end).is_some_and(is_text) {
+                return None;
+            }
+            let name = &window[..end];
+            let start = name.iter().rposition(|b| !is_text(b)).map_or(0, |i| i + 1);
+            std::str::from_utf8(&name[start..]).ok()
+        })
+        .filter(|s| s.len() > 4)
+        .map(|s| game_dir.join(s.replace('\\', "/")))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// Find the game's jars and launch options
+///
+/// In order of preference:
+/// - packr `config.json` next to the executable
+/// - The executable itself being a jar, or its embedded config referencing one
+/// - The largest jar in the game directory
+pub fn detect(game_exec_path: &Path) -> Result<JavaGame> {
+    let game_dir = game_exec_path.parent().unwrap_or(Path::new("."));
+    let mut vm_args = read_l4j_ini(game_exec_path);
+
+    let packr = std::fs::read_to_string(game_dir.join("config.json"))
+        .ok()
+        .and_then(|c| match serde_json::from_str::<PackrConfig>(&c) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                tracing::debug!("config.json is not a packr config: {:?}", e);
+                None
+            }
+        })
+        .filter(|config| config.jar.is_some() || !config.class_path.is_empty());
+
+    let mut jre_dirs: Vec<PathBuf> = BUNDLED_JRE_DIRS.iter().map(|d| game_dir.join(d)).collect();
+
+    let (class_path, main_class) = if let Some(packr) = packr {
+        tracing::info!("Found packr launcher config");
+        vm_args.extend(packr.vm_args);
+        if let Some(jre) = packr.jre_path {
+            jre_dirs.insert(0, game_dir.join(jre));
+        }
+        let class_path = packr
+            .jar
+            .iter()
+            .chain(packr.class_path.iter())
+            .map(|jar| game_dir.join(jar))
+            .collect();
+        (class_path, packr.main_class)
+    } else if is_zip(game_exec_path) {
+        tracing::info!("Executable is a jar wrapper, running it directly");
+        (vec![game_exec_path.to_path_buf()], None)
+    } else if let Some(jar) = jars_from_exe(game_exec_path, game_dir).into_iter().next() {
+        tracing::info!(?jar, "Found jar referenced by the executable");
+        (vec![jar], None)
+    } else {
+        let jar = std::fs::read_dir(game_dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("jar"))
+            })
+            .max_by_key(|p| p.metadata().map(|m| m.len()).unwrap_or_default())
+            .ok_or_else(|| stable_eyre::eyre::eyre!("Could not find a jar in {:?}", game_dir))?;
+        tracing::info!(
+            ?jar,
+            "Guessing main jar from the largest jar in the game directory"
+        );
+        (vec![jar], None)
+    };
+
+    let java_version = jre_dirs.iter().find_map(|dir| read_jre_version(dir));
+
+    Ok(JavaGame {
+        class_path,
+        main_class,
+        vm_args,
+        java_version,
+    })
+}
+
+/// Pick a host JRE for the game
+///
+/// Candidates are the given `java_homes`, i.e. the configured ones and `JAVA_HOME`. The same
+/// major version is preferred, then the closest newer one. Without any candidates, `java` from
+/// `$PATH` is used.
+pub fn select_java(required: Option<u32>, java_homes: &[PathBuf]) -> Result<String> {
+    let mut candidates: Vec<(PathBuf, u32)> = java_homes
+        .iter()
+        .filter(|home| home.join("bin/java").is_file())
+        .cloned()
+        .filter_map(|home| read_jre_version(&home).map(|v| (home, v)))
+        .collect();
+    candidates.sort_by_key(|(_, v)| *v);
+    tracing::debug!(?candidates, "Available host JREs");
+
+    let java = |home: &Path| home.join("bin/java").display().to_string();
+
+    let Some(required) = required else {
+        tracing::warn!("Could not detect the Java version the game was shipped with");
+        return Ok(candidates
+            .last()
+            .map(|(home, _)| java(home))
+            .unwrap_or_else(|| "java".to_string()));
+    };
+    tracing::info!("Game was shipped with Java {}", required);
+
+    if let Some((home, _)) = candidates.iter().find(|(_, v)| *v == required) {
+        tracing::info!(?home, "Using host JRE with matching version");
+        return Ok(java(home));
+    }
+    if let Some((home, version)) = candidates.iter().find(|(_, v)| *v > required) {
+        tracing::warn!(
+            ?home,
+            "No Java {} JRE found, using Java {} instead. This may not work!",
+            required,
+            version
+        );
+        return Ok(java(home));
+    }
+
+    if candidates.is_empty() {
+        tracing::warn!("No host JREs configured, using `java` from PATH");
+        return Ok("java".to_string());
+    }
+
+    Err(stable_eyre::eyre::eyre!(
+        "No JRE for Java {} or newer found, available: [{}]",
+        required,
+        candidates
+            .iter()
+            .map(|(home, v)| format!("{} (Java {})", home.display(), v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+/// Check if the game only bundles Windows LWJGL natives, either in its jars or loose in the game directory
+pub fn needs_lwjgl_natives(game: &JavaGame, game_dir: &Path) -> bool {
+    let mut names: Vec<String> = game
+        .class_path
+        .iter()
+        .filter_map(|jar| std::fs::File::open(jar).ok())
+        .filter_map(|f| zip::ZipArchive::new(f).ok())
+        .flat_map(|archive| archive.file_names().map(str::to_string).collect::<Vec<_>>())
+        .collect();
+    names.extend(
+        jwalk::WalkDir::new(game_dir)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string()),
+    );
+
+    let lwjgl = |ext: &str| {
+        names.iter().any(|n| {
+            let n = n.to_ascii_lowercase();
+            n.contains("lwjgl") && n.ends_with(ext)
+        })
+    };
+    lwjgl(".dll") && !lwjgl(".so")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_java_version() {
+        assert_eq!(parse_java_version("\"1.8.0_252\""), Some(8));
+        assert_eq!(parse_java_version("11.0.2"), Some(11));
+        assert_eq!(parse_java_version("\"17\""), Some(17));
+        assert_eq!(parse_java_version("21-ea"), Some(21));
+        assert_eq!(parse_java_version("garbage"), None);
+    }
+
+    #[test]
+    fn test_detect_packr_game() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let game_dir = temp_dir.path();
+        let exe = game_dir.join("SlayTheSpire.exe");
+        std::fs::write(&exe, b"MZ").unwrap();
+        std::fs::write(
+            game_dir.join("config.json"),
+            r#"{
+                "jrePath": "jre",
+                "classPath": ["desktop-1.0.jar"],
+                "mainClass": "com.megacrit.cardcrawl.desktop.DesktopLauncher",
+                "vmArgs": ["-Xmx1G"]
+            }"#,
+        )
+        .unwrap();
+        std::fs::create_dir(game_dir.join("jre")).unwrap();
+        std::fs::write(game_dir.join("jre/release"), "JAVA_VERSION=\"1.8.0_252\"\n").unwrap();
+
+        // Windows-only LWJGL natives in the jar
+        let mut jar =
+            zip::ZipWriter::new(std::fs::File::create(game_dir.join("desktop-1.0.jar")).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        jar.start_file("lwjgl64.dll", options).unwrap();
+        jar.write_all(b"MZ").unwrap();
+        jar.finish().unwrap();
+
+        let game = detect(&exe).unwrap();
+        assert_eq!(
+            game,
+            JavaGame {
+                class_path: vec![game_dir.join("desktop-1.0.jar")],
+                main_class: Some("com.megacrit.cardcrawl.desktop.DesktopLauncher".to_string()),
+                vm_args: vec!["-Xmx1G".to_string()],
+                java_version: Some(8),
+            }
+        );
+        assert!(needs_lwjgl_natives(&game, game_dir));
+    }
+
+    #[test]
+    fn test_jars_from_exe() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let game_dir = temp_dir.path();
+        std::fs::create_dir(game_dir.join("lib")).unwrap();
+        std::fs::write(game_dir.join("lib/game.jar"), b"PK").unwrap();
+        std::fs::write(game_dir.join("other.jar"), b"PK").unwrap();
+
+        let exe = game_dir.join("Game.exe");
+        std::fs::write(&exe, b"MZ\0\x01lib\\game.jar\0other.jarx\0").unwrap();
+        assert_eq!(
+            jars_from_exe(&exe, game_dir),
+            vec![game_dir.join("lib/game.jar")]
+        );
+    }
+
+    #[test]
+    fn test_select_java() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let homes: Vec<PathBuf> = [
+            ("jdk8", "1.8.0_392"),
+            ("jdk17", "17.0.9"),
+            ("jdk21", "21.0.1"),
+        ]
+        .iter()
+        .map(|(name, version)| {
+            let home = temp_dir.path().join(name);
+            std::fs::create_dir_all(home.join("bin")).unwrap();
+            std::fs::write(home.join("bin/java"), "").unwrap();
+            std::fs::write(
+                home.join("release"),
+                format!("JAVA_VERSION=\"{version}\"\n"),
+            )
+            .unwrap();
+            home
+        })
+        .collect();
+
+        let java = |home: &str| {
+            temp_dir
+                .path()
+                .join(home)
+                .join("bin/java")
+                .display()
+                .to_string()
+        };
+        assert_eq!(select_java(Some(8), &homes).unwrap(), java("jdk8"));
+        assert_eq!(select_java(Some(11), &homes).unwrap(), java("jdk17"));
+        assert!(select_java(Some(25), &homes).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Launch argument templating
+//!
+//! Builds the final argument list passed to the wrapper (or the game itself) from an
+//! argument template, so each game can control where the wrapper arguments, Steam's
+//! arguments and the game target end up.
+//!
+//! Template elements may contain the following placeholders:
+//!
+//! - `%exe%`: the resolved game target (e.g. the ASAR path for Electron games)
+//! - `%game_dir%`: the game's install directory
+//! - `%app_id%`: the Steam app ID
+//! - `%compat_data%`: the Steam compat data directory (`STEAM_COMPAT_DATA_PATH`)
+//!
+//! The following placeholders expand to a list of arguments, and must be used as
+//! a standalone template element:
+//!
+//! - `%wrapper_args%`: wrapper arguments from the config and the compat type
+//! - `%steam_args%`: arguments passed by Steam, after being rewritten by `arg_map`
+//! - `%append_args%`: `append_args` from the config
+use std::{collections::BTreeMap, path::PathBuf, process::Command};
+
+/// The default argument template, used when a game doesn't specify `arg_template`
+///
+/// The game target comes right after the wrapper arguments, so anything after it
+/// gets passed to the game instead of the runtime.
+pub const DEFAULT_ARG_TEMPLATE: [&str; 4] =
+    ["%wrapper_args%", "%exe%", "%steam_args%", "%append_args%"];
+
+/// Values substituted into an argument template
+#[derive(Debug, Default, Clone)]
+pub struct LaunchContext {
+    pub exe: PathBuf,
+    pub game_dir: Option<PathBuf>,
+    pub app_id: u32,
+    pub compat_data: Option<PathBuf>,
+    pub wrapper_args: Vec<String>,
+    pub steam_args: Vec<String>,
+    pub append_args: Vec<String>,
+}
+
+impl LaunchContext {
+    /// Substitute the scalar placeholders (`%exe%`, `%game_dir%`, `%app_id%`, `%compat_data%`)
+    /// inside a single argument
+    pub fn substitute(&self, arg: &str) -> String {
+        let display = |p: &Option<PathBuf>| {
+            p.as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default()
+        };
+        arg.replace("%exe%", &self.exe.display().to_string())
+            .replace("%game_dir%", &display(&self.game_dir))
+            .replace("%app_id%", &self.app_id.to_string())
+            .replace("%compat_data%", &display(&self.compat_data))
+    }
+
+    /// Expand an argument template into the final argument list
+    ///
+    /// If `has_wrapper` is false, standalone `%exe%` and `%wrapper_args%` elements are dropped.
+    /// There's no wrapper to take them, the game target is the program itself.
+    pub fn expand(&self, template: &[String], has_wrapper: bool) -> Vec<String> {
+        let mut args = Vec::new();
+        for element in template {
+            match element.as_str() {
+                "%exe%" | "%wrapper_args%" if !has_wrapper => {}
+                "%wrapper_args%" => {
+                    args.extend(self.wrapper_args.iter().map(|a| self.substitute(a)))
+                }
+                "%steam_args%" => args.extend(self.steam_args.iter().cloned()),
+                "%append_args%" => args.extend(self.append_args.iter().map(|a| self.substitute(a))),
+                other => args.push(self.substitute(other)),
+            }
+        }
+        args
+    }
+}
+
+/// Rewrite the arguments Steam passed to us using the game's `arg_map`
+///
+/// Each argument that matches a key in the map is replaced by the mapped arguments,
+/// an empty list drops the argument entirely. Matching is case-insensitive, as
+/// Windows games usually are.
+pub fn map_steam_args(args: &[String], arg_map: &BTreeMap<String, Vec<String>>) -> Vec<String> {
+    args.iter()
+        .flat_map(|arg| {
+            match arg_map
+                .iter()
+                .find(|(from, _)| from.eq_ignore_ascii_case(arg))
+            {
+                Some((from, to)) => {
+                    tracing::debug!(?from, ?to, "Rewriting Steam argument");
+                    to.clone()
+                }
+                None => vec![arg.clone()],
+            }
+        })
+        .collect()
+}
+
+/// Quote a string for a POSIX shell
+pub fn shell_quote(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+    {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Variables Steam sets for compat tools, which the launch relies on outside of `Command`'s own env
+pub fn steam_env() -> BTreeMap<String, String> {
+    std::env::vars()
+        .filter(|(key, _)| {
+            key.starts_with("STEAM_COMPAT_") || key == "SteamAppId" || key == "SteamGameId"
+        })
+        .collect()
+}
+
+/// Render a command as a standalone POSIX shell script
+///
+/// `inherited_env` is exported before the command's own variables, for the parts of the
+/// environment the command expects to inherit. `setup` holds the commands creating the files
+/// the launch needs, which run first.
+pub fn shell_script(
+    cmd: &Command,
+    inherited_env: &BTreeMap<String, String>,
+    setup: &[String],
+) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n# Generated by {} {}\nset -e\n\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    for command in setup {
+        script.push_str(command);
+        script.push('\n');
+    }
+    if !setup.is_empty() {
+        script.push('\n');
+    }
+
+    let mut exports: BTreeMap<String, Option<String>> = inherited_env
+        .iter()
+        .map(|(key, value)| (key.clone(), Some(value.clone())))
+        .collect();
+    for (key, value) in cmd.get_envs() {
+        exports.insert(
+            key.to_string_lossy().to_string(),
+            value.map(|v| v.to_string_lossy().to_string()),
+        );
+    }
+    for (key, value) in &exports {
+        match value {
+            Some(value) => script.push_str(&format!("export {}={}\n", key, shell_quote(value))),
+            None => script.push_str(&format!("unset {}\n", key)),
+        }
+    }
+
+    if let Some(dir) = cmd.get_current_dir() {
+        script.push_str(&format!("cd {}\n", shell_quote(&dir.to_string_lossy())));
+    }
+
+    let program = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| shell_quote(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    script.push_str(&format!("exec {program} \"$@\"\n"));
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn context() -> LaunchContext {
+        LaunchContext {
+            exe: PathBuf::from("/games/foo/resources/app.asar"),
+            game_dir: Some(PathBuf::from("/games/foo")),
+            app_id: 123456,
+            compat_data: Some(PathBuf::from("/compatdata/123456")),
+            wrapper_args: strings(&["--no-sandbox"]),
+            steam_args: strings(&["-windowed"]),
+            append_args: strings(&["--user-data-dir=%compat_data%/electron"]),
+        }
+    }
+
+    #[test]
+    fn test_default_template() {
+        let template = strings(&DEFAULT_ARG_TEMPLATE);
+        assert_eq!(
+            context().expand(&template, true),
+            strings(&[
+                "--no-sandbox",
+                "/games/foo/resources/app.asar",
+                "-windowed",
+                "--user-data-dir=/compatdata/123456/electron",
+            ])
+        );
+        // Without a wrapper, the wrapper arguments have nowhere to go
+        assert_eq!(
+            context().expand(&template, false),
+            strings(&["-windowed", "--user-data-dir=/compatdata/123456/electron",])
+        );
+    }
+
+    #[test]
+    fn test_previous_default_order() {
+        // The order used before templates, Steam's arguments before the game target
+        let template = strings(&["%wrapper_args%", "%steam_args%", "%exe%", "%append_args%"]);
+        assert_eq!(
+            context().expand(&template, true),
+            strings(&[
+                "--no-sandbox",
+                "-windowed",
+                "/games/foo/resources/app.asar",
+                "--user-data-dir=/compatdata/123456/electron",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let template = strings(&[
+            "%steam_args%",
+            "--app=%exe%",
+            "--id",
+            "%app_id%",
+            "--cwd=%game_dir%",
+        ]);
+        assert_eq!(
+            context().expand(&template, true),
+            strings(&[
+                "-windowed",
+                "--app=/games/foo/resources/app.asar",
+                "--id",
+                "123456",
+                "--cwd=/games/foo",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_map_steam_args() {
+        let arg_map: BTreeMap<String, Vec<String>> = [
+            ("-windowed".to_string(), strings(&["--windowed"])),
+            ("-novid".to_string(), vec![]),
+            (
+                "-fullscreen".to_string(),
+                strings(&["--fullscreen", "--borderless"]),
+            ),
+        ]
+        .into();
+        assert_eq!(
+            map_steam_args(
+                &strings(&["-Windowed", "-novid", "-fullscreen", "-other"]),
+                &arg_map
+            ),
+            strings(&["--windowed", "--fullscreen", "--borderless", "-other"])
+        );
+    }
+
+    #[test]
+    fn test_shell_script() {
+        assert_eq!(shell_quote("/usr/bin/electron"), "/usr/bin/electron");
+        assert_eq!(shell_quote("My Game"), "'My Game'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+
+        let mut cmd = Command::new("/usr/bin/love");
+        cmd.arg("/games/My Game/game.love")
+            .env("LD_PRELOAD", "")
+            .env("LUA_PATH", "/boson/lib/love/?.lua;;")
+            .env_remove("DISPLAY")
+            .current_dir("/games/My Game");
+        let inherited: BTreeMap<String, String> =
+            [("SteamAppId".to_string(), "2379780".to_string())].into();
+        let setup = vec!["mkdir -p /compatdata/boson".to_string()];
+        let script = shell_script(&cmd, &inherited, &setup);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("set -e\n\nmkdir -p /compatdata/boson\n\n"));
+        assert!(script.ends_with(
+            "unset DISPLAY\n\
+             export LD_PRELOAD=''\n\
+             export LUA_PATH='/boson/lib/love/?.lua;;'\n\
+             export SteamAppId=2379780\n\
+             cd '/games/My Game'\n\
+             exec /usr/bin/love '/games/My Game/game.love' \"$@\"\n"
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+//! Logging setup and per-game log files
+//!
+//! With `BOSON_LOG=1`, like Proton's `PROTON_LOG`, each launch writes a log file to
+//! `~/.local/state/boson/logs/<appid>-<timestamp>.log` holding Boson's own traces and the
+//! game's output, so users can attach a single file to bug reports.
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Arc, OnceLock},
+    thread::JoinHandle,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use stable_eyre::Result;
+use tracing_subscriber::{fmt::writer::MakeWriterExt, EnvFilter};
+
+#[cfg(not(debug_assertions))]
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+#[cfg(debug_assertions)]
+pub const DEFAULT_LOG_LEVEL: &str = "trace";
+
+/// How many log files are kept per game by default, override with `BOSON_LOG_KEEP`
+pub const DEFAULT_LOG_KEEP: usize = 10;
+
+/// The log file of this launch, if per-game logs are enabled
+static LOG_FILE: OnceLock<Arc<File>> = OnceLock::new();
+
+/// Whether per-game log files are enabled, through `BOSON_LOG`
+pub fn log_enabled() -> bool {
+    std::env::var("BOSON_LOG").is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Pick the log filter, from `BOSON_LOG_LEVEL`, `RUST_LOG` or `STEAM_COMPAT_TRACING`
+///
+/// Steam sets `STEAM_COMPAT_TRACING=1` when tracing is enabled for compat tools,
+/// which turns on trace logging. Anything else in it is used as a filter directly.
+pub fn log_filter(steam_tracing: Option<&str>) -> String {
+    let from_env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+    from_env("BOSON_LOG_LEVEL")
+        .or_else(|| from_env("RUST_LOG"))
+        .or_else(|| match steam_tracing {
+            Some("1") => Some("trace".to_string()),
+            Some("0") | Some("") | None => None,
+            Some(filter) => Some(filter.to_string()),
+        })
+        .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string())
+}
+
+/// Directory per-game logs are written to
+pub fn logs_dir() -> PathBuf {
+    dirs::state_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join(".local/state")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("boson")
+        .join("logs")
+}
+
+/// Format a UNIX timestamp as a sortable UTC timestamp, e.g. `20240131-235959`
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let time = secs % 86400;
+
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Remove the oldest logs of a game, keeping the `keep` most recent ones
+pub fn rotate_logs(dir: &Path, app_id: u32, keep: usize) {
+    let prefix = format!("{app_id}-");
+    let mut logs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension().is_some_and(|ext| ext == "log")
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&prefix))
+        })
+        .collect();
+    // Timestamps sort lexicographically
+    logs.sort();
+    let excess = logs.len().saturating_sub(keep);
+    for old in &logs[..excess] {
+        if let Err(e) = std::fs::remove_file(old) {
+            tracing::warn!(?old, "Could not remove old log: {}", e);
+        }
+    }
+}
+
+/// Create a new log file for a game launch, rotating out old ones
+pub fn open_log(dir: &Path, app_id: u32) -> Result<(PathBuf, Arc<File>)> {
+    std::fs::create_dir_all(dir)?;
+    let keep = std::env::var("BOSON_LOG_KEEP")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_LOG_KEEP);
+    // Make room for the new log
+    rotate_logs(dir, app_id, keep.saturating_sub(1));
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!("{app_id}-{}.log", format_timestamp(now)));
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    Ok((path, Arc::new(file)))
+}
+
+/// Set up logging to stderr, and to the log file if there is one
+pub fn init(filter: &str, log_file: Option<Arc<File>>) {
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(filter))
+        .without_time();
+    match log_file {
+        Some(log_file) => {
+            let _ = LOG_FILE.set(log_file.clone());
+            subscriber
+                .with_writer(std::io::stderr.and(log_file))
+                .with_ansi(false)
+                .init()
+        }
+        // stdout is reserved for verb output, e.g. translated paths for Steam
+        None => subscriber.with_writer(std::io::stderr).init(),
+    }
+}
+
+/// The log file of this launch, if per-game logs are enabled
+pub fn log_file() -> Option<Arc<File>> {
+    LOG_FILE.get().cloned()
+}
+
+/// Copies of the game's output being written to the log file
+pub struct Tee {
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Tee {
+    /// Capture the game's stdout and stderr, passing them through while also writing them
+    /// to the log file, with each line prefixed by the stream it came from
+    pub fn capture(cmd: &mut Command, log_file: Arc<File>) -> Result<Self> {
+        let (stdout_reader, stdout_writer) = std::io::pipe()?;
+        let (stderr_reader, stderr_writer) = std::io::pipe()?;
+        cmd.stdout(stdout_writer).stderr(stderr_writer);
+
+        let copy = |reader: std::io::PipeReader,
+                    mut output: Box<dyn Write + Send>,
+                    stream: &'static str| {
+            let log_file = log_file.clone();
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(reader);
+                let mut line = Vec::new();
+                while reader
+                    .read_until(b'\n', &mut line)
+                    .is_ok_and(|read| read > 0)
+                {
+                    let _ = output.write_all(&line).and_then(|_| output.flush());
+                    let mut prefixed = format!("[{stream}] ").into_bytes();
+                    prefixed.extend(&line);
+                    if !prefixed.ends_with(b"\n") {
+                        prefixed.push(b'\n');
+                    }
+                    let _ = (&*log_file).write_all(&prefixed);
+                    line.clear();
+                }
+            })
+        };
+
+        Ok(Self {
+            threads: vec![
+                copy(stdout_reader, Box::new(std::io::stdout()), "stdout"),
+                copy(stderr_reader, Box::new(std::io::stderr()), "stderr"),
+            ],
+        })
+    }
+
+    /// Wait for the remaining output to be written
+    ///
+    /// The write ends of the pipes must be closed first, by the game exiting and
+    /// the command's copies being replaced.
+    pub fn finish(self) {
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_rotation() {
+        assert_eq!(format_timestamp(0), "19700101-000000");
+        assert_eq!(format_timestamp(1709251199), "20240229-235959");
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for timestamp in ["20240101-000000", "20240102-000000", "20240103-000000"] {
+            std::fs::write(temp_dir.path().join(format!("42-{timestamp}.log")), "").unwrap();
+        }
+        std::fs::write(temp_dir.path().join("7-20230101-000000.log"), "").unwrap();
+
+        rotate_logs(temp_dir.path(), 42, 2);
+        let mut remaining: Vec<String> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            vec![
+                "42-20240102-000000.log",
+                "42-20240103-000000.log",
+                "7-20230101-000000.log"
+            ]
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! LOVE support
+//!
+//! Windows LOVE games are `love.exe` with the game's `.love` zip appended (a "fused" game),
+//! shipped next to `love.dll` and friends. LOVE breaks compatibility between releases,
+//! so the game has to run on a host LOVE of the version it was made for.
+//!
+//! The fused archive is extracted into a clean `.love` file before launching, as LOVE would
+//! otherwise see the Windows executable as the game's source.
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use stable_eyre::Result;
+
+use crate::{path_search, pe};
+
+/// Zip end of central directory record signature, "PK\x05\x06"
+const EOCD_SIGNATURE: [u8; 4] = *b"PK\x05\x06";
+
+/// Zip local file header signature, "PK\x03\x04"
+const LOCAL_HEADER_SIGNATURE: [u8; 4] = *b"PK\x03\x04";
+
+/// Zip central directory file header signature, "PK\x01\x02"
+const CENTRAL_DIRECTORY_SIGNATURE: [u8; 4] = *b"PK\x01\x02";
+
+/// The EOCD record is 22 bytes, followed by a comment of up to 65535 bytes
+const EOCD_SEARCH_LEN: u64 = 22 + 65535;
+
+/// A LOVE version, e.g. `11.5` or `0.10.2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LoveVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl LoveVersion {
+    /// Parse a version string, missing components default to 0
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().split('.').map(|p| p.parse::<u32>());
+        Some(Self {
+            major: parts.next()?.ok()?,
+            minor: parts.next().unwrap_or(Ok(0)).ok()?,
+            patch: parts.next().unwrap_or(Ok(0)).ok()?,
+        })
+    }
+
+    /// The release series games are compatible within: `0.10` before LOVE 11, `11` after
+    pub fn series(&self) -> String {
+        if self.major == 0 {
+            format!("0.{}", self.minor)
+        } else {
+            self.major.to_string()
+        }
+    }
+}
+
+impl std::fmt::Display for LoveVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Where the LOVE version of a game was detected from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionSource {
+    /// `t.version` in the game's `conf.lua`
+    ConfLua,
+    /// The version resource of the bundled `love.dll`
+    LoveDll,
+}
+
+impl std::fmt::Display for VersionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionSource::ConfLua => write!(f, "t.version in conf.lua"),
+            VersionSource::LoveDll => write!(f, "love.dll version resource"),
+        }
+    }
+}
+
+pub fn env_love_path() -> String {
+    std::env::var("LOVE_PATH").unwrap_or_else(|_| "love".to_string())
+}
+
+/// Find `t.version = "x.y"` in a `conf.lua`
+pub fn parse_conf_version(conf: &str) -> Option<LoveVersion> {
+    conf.lines()
+        .map(|line| line.split("--").next().unwrap_or_default())
+        .find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            if !key.trim().ends_with(".version") {
+                return None;
+            }
+            let value = value.trim().trim_end_matches([';', ',']).trim();
+            LoveVersion::parse(value.trim_matches(['"', '\'']))
+        })
+}
+
+/// Read `conf.lua` from the game, either the archive fused into the executable
+/// or a loose game directory next to it
+fn read_conf_lua(game_exec_path: &Path) -> Option<String> {
+    let from_archive = std::fs::File::open(game_exec_path)
+        .ok()
+        .and_then(|f| zip::ZipArchive::new(f).ok())
+        .and_then(|mut archive| {
+            let mut conf = String::new();
+            archive
+                .by_name("conf.lua")
+                .ok()?
+                .read_to_string(&mut conf)
+                .ok()?;
+            Some(conf)
+        });
+
+    from_archive.or_else(|| {
+        let game_dir = game_exec_path.parent()?;
+        std::fs::read_to_string(game_dir.join("conf.lua")).ok()
+    })
+}
+
+/// Read the LOVE version from the version resource of `love.dll`
+pub fn read_dll_version(dll: &Path) -> Option<LoveVersion> {
+    let [major, minor, patch, _] = pe::file_version(dll)?;
+    Some(LoveVersion {
+        major,
+        minor,
+        patch,
+    })
+}
+
+/// Detect the LOVE version the game was made for
+///
+/// `conf.lua` is preferred as it's what the game asks for, `love.dll` is what it shipped with.
+pub fn detect_version(game_exec_path: &Path) -> Option<(LoveVersion, VersionSource)> {
+    if let Some(version) = read_conf_lua(game_exec_path).and_then(|c| parse_conf_version(&c)) {
+        return Some((version, VersionSource::ConfLua));
+    }
+
+    let dll = game_exec_path.parent()?.join("love.dll");
+    read_dll_version(&dll).map(|version| (version, VersionSource::LoveDll))
+}
+
+/// Pick a host LOVE binary for the game's version
+///
+/// `love_binaries` maps versions (e.g. `"11"`, `"11.4"`, `"0.10"`, `"0.10.2"`) to binaries,
+/// the most specific match wins. Otherwise `LOVE_PATH` or `love` from `$PATH` is used.
+pub fn select_binary(
+    version: Option<LoveVersion>,
+    love_binaries: &BTreeMap<String, String>,
+) -> String {
+    let Some(version) = version else {
+        tracing::warn!("Could not detect the game's LOVE version, using the default LOVE");
+        return env_love_path();
+    };
+
+    let candidates = [
+        version.to_string(),
+        format!("{}.{}", version.major, version.minor),
+        version.series(),
+    ];
+    for key in candidates.iter() {
+        if let Some(binary) = love_binaries.get(key) {
+            tracing::info!(version = %key, ?binary, "Using configured LOVE binary");
+            return binary.clone();
+        }
+    }
+
+    let versioned = format!("love-{}", version.series());
+    if path_search::find_in_path(&versioned).is_some() {
+        tracing::info!(binary = ?versioned, "Using versioned LOVE binary from PATH");
+        return versioned;
+    }
+
+    tracing::warn!(
+        %version,
+        "No LOVE binary configured for this version, falling back to the default LOVE"
+    );
+    env_love_path()
+}
+
+/// Find the offset of the zip archive fused into a LOVE executable
+///
+/// Like PhysFS, this goes from the end of central directory record: the central directory
+/// is right before it, and comparing its real position with the offset recorded in the
+/// record gives the size of whatever was prepended to the archive.
+///
+/// Archives fixed up with `zip -A` record offsets from the start of the executable instead,
+/// so the whole executable is already a valid archive and the offset is 0.
+///
+/// Returns `None` if the file doesn't end with a zip archive.
+pub fn find_fused_archive(game_exec_path: &Path) -> Result<Option<u64>> {
+    let mut file = File::open(game_exec_path)?;
+    let len = file.metadata()?.len();
+    let search_len = len.min(EOCD_SEARCH_LEN);
+    if search_len < 22 {
+        return Ok(None);
+    }
+
+    let mut tail = vec![0u8; search_len as usize];
+    file.seek(SeekFrom::End(-(search_len as i64)))?;
+    file.read_exact(&mut tail)?;
+    let Some(eocd) = tail.windows(4).rposition(|w| w == EOCD_SIGNATURE) else {
+        return Ok(None);
+    };
+    let Some(record) = tail.get(eocd..eocd + 22) else {
+        return Ok(None);
+    };
+
+    let cd_size = u32::from_le_bytes(record[12..16].try_into()?) as u64;
+    let cd_offset = u32::from_le_bytes(record[16..20].try_into()?) as u64;
+    let eocd_pos = len - search_len + eocd as u64;
+    let Some(offset) = eocd_pos
+        .checked_sub(cd_size)
+        .and_then(|cd_start| cd_start.checked_sub(cd_offset))
+    else {
+        tracing::warn!(?game_exec_path, "Zip central directory is out of bounds");
+        return Ok(None);
+    };
+
+    let mut signature = [0u8; 4];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut signature)?;
+    if offset == 0 && signature != LOCAL_HEADER_SIGNATURE {
+        // Offsets are absolute, check the central directory is where the record says it is
+        file.seek(SeekFrom::Start(cd_offset))?;
+        file.read_exact(&mut signature)?;
+        if signature == CENTRAL_DIRECTORY_SIGNATURE {
+            tracing::debug!(?game_exec_path, "Fused archive uses absolute offsets");
+            return Ok(Some(0));
+        }
+    }
+    if signature != LOCAL_HEADER_SIGNATURE {
+        tracing::warn!(
+            ?game_exec_path,
+            offset,
+            "Fused archive offset doesn't point to a zip entry"
+        );
+        return Ok(None);
+    }
+    Ok(Some(offset))
+}
+
+/// Where the archive fused into a LOVE executable is extracted to in the cache directory
+///
+/// The path is keyed by the executable's size and modification time, like cached SWFs,
+/// so a game update gets extracted again without reading the whole executable every launch.
+pub fn fused_path(game_exec_path: &Path, cache_dir: &Path) -> Result<PathBuf> {
+    let metadata = game_exec_path.metadata()?;
+    let modified = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let key = format!("{:x}-{:x}", metadata.len(), modified.as_nanos());
+    let name = game_exec_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "game".to_string());
+    Ok(cache_dir.join(key).join(format!("{name}.love")))
+}
+
+/// Extract the archive fused into a LOVE executable to `love_path`, from [`fused_path`]
+///
+/// Older extractions in the cache directory are removed. Files next to the executable are linked
+/// next to the archive, so the game can still mount them relative to its source directory.
+pub fn extract_fused(game_exec_path: &Path, offset: u64, love_path: &Path) -> Result<()> {
+    let (Some(dir), Some(name)) = (love_path.parent(), love_path.file_stem()) else {
+        return Err(stable_eyre::eyre::eyre!(
+            "Invalid .love archive path {:?}",
+            love_path
+        ));
+    };
+    let cache_dir = dir.parent().unwrap_or(dir);
+    let name = name.to_string_lossy();
+
+    if love_path.is_file() {
+        tracing::debug!(?love_path, "Using cached .love archive");
+        return Ok(());
+    }
+
+    if cache_dir.is_dir() {
+        for stale in std::fs::read_dir(cache_dir)?.filter_map(|e| e.ok()) {
+            tracing::debug!(path = ?stale.path(), "Removing stale .love extraction");
+            std::fs::remove_dir_all(stale.path())?;
+        }
+    }
+
+    tracing::info!(
+        ?game_exec_path,
+        ?love_path,
+        "Extracting fused .love archive"
+    );
+    std::fs::create_dir_all(dir)?;
+    let mut file = File::open(game_exec_path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    // Extract to a temporary name first, so an interrupted extraction isn't picked up as cached
+    let partial = dir.join(format!("{name}.love.partial"));
+    std::io::copy(&mut file, &mut File::create(&partial)?)?;
+
+    if let Some(game_dir) = game_exec_path.parent() {
+        for entry in std::fs::read_dir(game_dir)?.filter_map(|e| e.ok()) {
+            let link = dir.join(entry.file_name());
+            if entry.path() == game_exec_path || link == love_path || link.exists() {
+                continue;
+            }
+            std::os::unix::fs::symlink(entry.path(), link)?;
+        }
+    }
+
+    std::fs::rename(&partial, love_path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conf_version() {
+        let conf = r#"
+function love.conf(t)
+    t.identity = "Balatro"
+    -- t.version = "0.9.0"
+    t.version = "11.4"
+    t.window.title = "Balatro"
+end
+"#;
+        assert_eq!(
+            parse_conf_version(conf),
+            Some(LoveVersion {
+                major: 11,
+                minor: 4,
+                patch: 0
+            })
+        );
+        assert_eq!(
+            parse_conf_version("c.version = '0.10.2';").map(|v| v.series()),
+            Some("0.10".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dll_version_and_select_binary() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dll = temp_dir.path().join("love.dll");
+        let mut bytes = b"MZ fake love.dll".to_vec();
+        bytes.extend(pe::FIXED_FILE_INFO_SIGNATURE.to_le_bytes());
+        bytes.extend(0x0001_0000u32.to_le_bytes());
+        bytes.extend(0x0000_000au32.to_le_bytes());
+        bytes.extend(0x0002_0000u32.to_le_bytes());
+        std::fs::write(&dll, bytes).unwrap();
+
+        let version = read_dll_version(&dll).unwrap();
+        assert_eq!(version.to_string(), "0.10.2");
+
+        let binaries: BTreeMap<String, String> = [
+            ("0.10".to_string(), "/opt/love010/love".to_string()),
+            ("11".to_string(), "/usr/bin/love".to_string()),
+        ]
+        .into();
+        assert_eq!(select_binary(Some(version), &binaries), "/opt/love010/love");
+        let eleven = LoveVersion::parse("11.5").unwrap();
+        assert_eq!(select_binary(Some(eleven), &binaries), "/usr/bin/love");
+    }
+
+    #[test]
+    fn test_extract_fused_archive() {
+        use std::io::Write;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let game_dir = temp_dir.path().join("game");
+        std::fs::create_dir(&game_dir).unwrap();
+        std::fs::write(game_dir.join("dlc.zip"), b"PK").unwrap();
+
+        let mut love = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        love.start_file("conf.lua", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        love.write_all(b"function love.conf(t)\n    t.version = \"11.5\"\nend\n")
+            .unwrap();
+        let love = love.finish().unwrap().into_inner();
+
+        let exe = game_dir.join("Balatro.exe");
+        let prefix = b"MZ fake love.exe";
+        std::fs::write(&exe, [prefix.as_slice(), &love].concat()).unwrap();
+
+        let offset = find_fused_archive(&exe).unwrap().unwrap();
+        assert_eq!(offset, prefix.len() as u64);
+        assert_eq!(detect_version(&exe).unwrap().1, VersionSource::ConfLua);
+
+        let cache_dir = temp_dir.path().join("cache");
+        let extracted = fused_path(&exe, &cache_dir).unwrap();
+        assert!(!cache_dir.exists());
+        extract_fused(&exe, offset, &extracted).unwrap();
+        assert_eq!(std::fs::read(&extracted).unwrap(), love);
+        assert!(extracted.with_file_name("dlc.zip").exists());
+        extract_fused(&exe, offset, &extracted).unwrap();
+        assert_eq!(std::fs::read(&extracted).unwrap(), love);
+
+        // A game update gets extracted again, replacing the old extraction
+        std::fs::write(&exe, [b"MZ updated".as_slice(), &love].concat()).unwrap();
+        let offset = find_fused_archive(&exe).unwrap().unwrap();
+        let updated = fused_path(&exe, &cache_dir).unwrap();
+        extract_fused(&exe, offset, &updated).unwrap();
+        assert_ne!(updated, extracted);
+        assert!(!extracted.exists());
+
+        let other = game_dir.join("other.exe");
+        std::fs::write(&other, b"MZ not a fused game").unwrap();
+        assert!(find_fused_archive(&other).unwrap().is_none());
+
+        // Fixed up with `zip -A`: every offset is shifted to count from the start of the exe
+        let mut fixed_up = [prefix.as_slice(), &love].concat();
+        let shift = |bytes: &mut [u8], at: usize| {
+            let value = u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+            bytes[at..at + 4].copy_from_slice(&(value + prefix.len() as u32).to_le_bytes());
+        };
+        let eocd = fixed_up.len() - 22;
+        let mut entry = u32::from_le_bytes(fixed_up[eocd + 16..eocd + 20].try_into().unwrap())
+            as usize
+            + prefix.len();
+        while fixed_up[entry..entry + 4] == CENTRAL_DIRECTORY_SIGNATURE {
+            shift(&mut fixed_up, entry + 42);
+            let field = |at: usize| u16::from_le_bytes([fixed_up[at], fixed_up[at + 1]]) as usize;
+            entry += 46 + field(entry + 28) + field(entry + 30) + field(entry + 32);
+        }
+        shift(&mut fixed_up, eocd + 16);
+        std::fs::write(&other, &fixed_up).unwrap();
+        assert_eq!(find_fused_archive(&other).unwrap(), Some(0));
+        zip::ZipArchive::new(std::fs::File::open(&other).unwrap())
+            .unwrap()
+            .by_name("conf.lua")
+            .unwrap();
+    }
+}
@@ -3,14 +3,26 @@ use stable_eyre::Result;
 use std::path::PathBuf;
 
 use crate::config::BosonConfig;
+mod asar;
 pub mod config;
+mod electron;
+mod flash;
+mod godot;
+mod hashlink;
+mod java;
+mod launch_args;
+mod logging;
+mod love;
+mod mkxp;
+mod mono;
 mod path_search;
+mod pe;
+mod provenance;
+mod renpy;
 mod runtime;
-// use tracing_subscriber::;
-#[cfg(not(debug_assertions))]
-const DEFAULT_LOG_LEVEL: &str = "info";
-#[cfg(debug_assertions)]
-const DEFAULT_LOG_LEVEL: &str = "trace";
+mod runtimes;
+mod steam;
+mod supervise;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -23,40 +35,128 @@ pub struct Boson {
     #[clap(flatten)]
     pub steam_opts: config::SteamCompatConfig,
 }
+#[derive(clap::Args)]
+pub struct RunArgs {
+    /// Print the resolved launch as a shell script instead of running the game
+    #[clap(long, env = "BOSON_DRY_RUN", value_parser = config::parse_bool)]
+    dry_run: bool,
+
+    game_path: PathBuf,
+    // do not parse any further, treat all further arguments here as just vec of strings
+    // e.g unknown args get added here
+    #[clap(trailing_var_arg = true)]
+    #[clap(allow_hyphen_values = true)]
+    additional_args: Vec<String>,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
-    #[command(alias = "waitforexitandrun")]
     /// Launch the game with Boson, injecting hooks
-    Run {
-        game_path: PathBuf,
-        // do not parse any further, treat all further arguments here as just vec of strings
-        // e.g unknown args get added here
-        #[clap(trailing_var_arg = true)]
-        #[clap(allow_hyphen_values = true)]
-        additional_args: Vec<String>,
-    },
+    Run(RunArgs),
+
+    /// Launch the game like `run`, Steam uses this verb for the game's first process
+    #[command(name = "waitforexitandrun")]
+    WaitForExitAndRun(RunArgs),
 
     /// Get the game path for a given executable
     Path { path: PathBuf },
+
+    /// Translate host paths into paths the game understands (`Z:\` and `C:\` style paths)
+    #[command(name = "getcompatpath")]
+    GetCompatPath {
+        #[clap(required = true)]
+        paths: Vec<String>,
+    },
+
+    /// Translate paths from the game (`Z:\` and `C:\` style paths) into host paths
+    #[command(name = "getnativepath")]
+    GetNativePath {
+        #[clap(required = true)]
+        paths: Vec<String>,
+    },
+
+    /// Manage runtimes downloaded by Boson
+    Runtime {
+        #[clap(subcommand)]
+        cmd: RuntimeCommands,
+    },
+
+    /// Inspect Boson's configuration
+    Config {
+        #[clap(subcommand)]
+        cmd: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Show the merged config of a game, with where each setting comes from
+    Show {
+        /// Steam app ID of the game
+        app_id: u32,
+        #[clap(long, value_enum, default_value = "toml")]
+        format: ConfigFormat,
+    },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+#[derive(Subcommand)]
+pub enum RuntimeCommands {
+    /// Download and install a runtime into `~/.local/share/boson/runtimes`
+    Install {
+        runtime: RuntimeKind,
+        /// Version to install, e.g. `30.0.1`
+        #[clap(value_name = "VERSION")]
+        runtime_version: String,
+        /// Base URL of the release mirror, `file://` URLs are supported for local mirrors
+        #[clap(long, env = "ELECTRON_MIRROR", default_value = runtimes::DEFAULT_ELECTRON_MIRROR)]
+        mirror: String,
+    },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum RuntimeKind {
+    Electron,
 }
 
 fn main() -> Result<()> {
     stable_eyre::install()?;
 
-    tracing_subscriber::fmt()
-        .with_env_filter(DEFAULT_LOG_LEVEL)
-        .without_time()
-        .init();
+    let args = Boson::parse();
+    let app_id = args.steam_opts.get_app_id().unwrap_or_default();
+
+    let log_filter = logging::log_filter(args.steam_opts.tracing.as_deref());
+    // Only game launches get a log file, not Steam's path queries
+    let log_file = match &args.cmd {
+        Commands::Run(_) | Commands::WaitForExitAndRun(_) if logging::log_enabled() => {
+            match logging::open_log(&logging::logs_dir(), app_id) {
+                Ok(log_file) => Some(log_file),
+                Err(e) => {
+                    eprintln!("Could not create a log file: {e}");
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    logging::init(&log_filter, log_file.as_ref().map(|(_, file)| file.clone()));
 
     let appname = env!("CARGO_PKG_NAME");
     let appversion = env!("CARGO_PKG_VERSION");
     // print the args
-    tracing::info!("{appname} {appversion} starting up, logging at {DEFAULT_LOG_LEVEL} level.");
+    tracing::info!("{appname} {appversion} starting up, logging at {log_filter} level.");
+    if let Some((path, _)) = &log_file {
+        tracing::info!("Logging to {:?}", path);
+    }
     tracing::info!(
         "Launched with args: {:?}",
         std::env::args().collect::<Vec<String>>()
     );
-    let args = Boson::parse();
     let exec_path = std::env::current_exe()?;
 
     // get the folder of the executable
@@ -73,12 +173,18 @@ fn main() -> Result<()> {
         tracing::trace!("{:#?}", args.steam_opts)
     });
     let bosoncfg = BosonConfig::load()?;
-    let app_id = args.steam_opts.get_app_id().unwrap_or_default();
+    // Keep the verb Steam invoked us with, so it can be forwarded to deferred tools
+    let verb = match &args.cmd {
+        Commands::WaitForExitAndRun(_) => runtime::Verb::WaitForExitAndRun,
+        _ => runtime::Verb::Run,
+    };
     match args.cmd {
-        Commands::Run {
-            game_path,
-            additional_args,
-        } => {
+        Commands::Run(run_args) | Commands::WaitForExitAndRun(run_args) => {
+            let RunArgs {
+                dry_run,
+                game_path,
+                additional_args,
+            } = run_args;
             // todo: Move this to another function
             tracing::info!("Running game at path: {:?}", game_path);
 
@@ -94,13 +200,56 @@ fn main() -> Result<()> {
             tracing::debug!("Determining runtime for game");
             let runtime = runtime::Runtime::new(args.steam_opts, gamecfg, game_path);
 
-            runtime.launch_game(additional_args)?;
-            Ok(())
+            if dry_run {
+                print!("{}", runtime.dry_run(verb, additional_args)?);
+                return Ok(());
+            }
+
+            // Exit with the game's own status, so whatever launched us can tell a crashed game
+            // apart from Boson failing to launch it
+            let status = runtime.launch_game(verb, additional_args)?;
+            std::process::exit(supervise::exit_code(&status));
         }
         Commands::Path { path } => {
-            let game_path = path_search::get_game_path(&path);
+            let game_path = path_search::get_game_path(&path)?;
             println!("{}", game_path.display());
             Ok(())
         }
+        Commands::GetCompatPath { paths } => runtime::translate_paths(
+            &args.steam_opts,
+            &bosoncfg.get_game_config(app_id),
+            runtime::Verb::GetCompatPath,
+            &paths,
+        ),
+        Commands::GetNativePath { paths } => runtime::translate_paths(
+            &args.steam_opts,
+            &bosoncfg.get_game_config(app_id),
+            runtime::Verb::GetNativePath,
+            &paths,
+        ),
+        Commands::Runtime {
+            cmd:
+                RuntimeCommands::Install {
+                    runtime: RuntimeKind::Electron,
+                    runtime_version,
+                    mirror,
+                },
+        } => {
+            let install_dir =
+                runtimes::install_electron(&runtime_version, &mirror, &runtimes::runtimes_dir())?;
+            println!("{}", install_dir.display());
+            Ok(())
+        }
+        Commands::Config {
+            cmd: ConfigCommands::Show { app_id, format },
+        } => {
+            let (gamecfg, provenance) = bosoncfg.get_game_config_provenance(app_id)?;
+            let output = match format {
+                ConfigFormat::Toml => provenance::to_annotated_toml(&gamecfg, &provenance)?,
+                ConfigFormat::Json => provenance::to_annotated_json(&gamecfg, &provenance)?,
+            };
+            println!("{}", output.trim_end());
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,234 @@
+//! RPG Maker XP/VX/VX Ace support via mkxp-z
+//!
+//! RGSS games are a `Game.exe` player, a `Game.ini` and the game data (optionally packed into a
+//! `Game.rgss*a` archive). mkxp-z reimplements the player, we just need to tell it where
+//! the game is through a generated `mkxp.json`.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use stable_eyre::Result;
+
+/// Common places distributions install General MIDI soundfonts
+const SYSTEM_SOUNDFONTS: [&str; 5] = [
+    "/usr/share/soundfonts/default.sf2",
+    "/usr/share/soundfonts/FluidR3_GM.sf2",
+    "/usr/share/sounds/sf2/FluidR3_GM.sf2",
+    "/usr/share/sounds/sf2/default-GM.sf2",
+    "/usr/share/soundfonts/freepats-general-midi.sf2",
+];
+
+pub fn env_mkxp_path() -> String {
+    std::env::var("MKXP_PATH").unwrap_or_else(|_| "mkxp-z".to_string())
+}
+
+/// An RGSS game, from its `Game.ini`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgssGame {
+    pub game_dir: PathBuf,
+    /// RGSS version: 1 for XP, 2 for VX, 3 for VX Ace
+    pub rgss_version: u8,
+    pub title: Option<String>,
+    /// Encrypted game archive, if the game is packed
+    pub archive: Option<PathBuf>,
+    /// RTP names the game depends on, e.g. `RPGVXAce`
+    pub rtps: Vec<String>,
+}
+
+/// Parse the `[Game]` section of `Game.ini` into key/value pairs
+fn parse_ini(contents: &str) -> BTreeMap<String, String> {
+    let mut in_game_section = false;
+    let mut values = BTreeMap::new();
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_game_section = line.eq_ignore_ascii_case("[game]");
+        } else if in_game_section {
+            if let Some((key, value)) = line.split_once('=') {
+                values.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+    }
+    values
+}
+
+/// Guess the RGSS version from the `Library` DLL name (`RGSS104E.dll`, `System\\RGSS301.dll`)
+/// or the scripts file extension
+fn rgss_version(ini: &BTreeMap<String, String>) -> Option<u8> {
+    if let Some(library) = ini.get("library") {
+        let library = library.to_ascii_uppercase();
+        if let Some(index) = library.find("RGSS") {
+            match library.as_bytes().get(index + 4) {
+                Some(b'1') => return Some(1),
+                Some(b'2') => return Some(2),
+                Some(b'3') => return Some(3),
+                _ => {}
+            }
+        }
+    }
+
+    let scripts = ini.get("scripts")?.to_ascii_lowercase();
+    if scripts.ends_with(".rxdata") {
+        Some(1)
+    } else if scripts.ends_with(".rvdata") {
+        Some(2)
+    } else if scripts.ends_with(".rvdata2") {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// Detect an RGSS game from `Game.ini` next to the executable
+pub fn detect(game_exec_path: &Path) -> Result<RgssGame> {
+    let game_dir = game_exec_path.parent().unwrap_or(Path::new("."));
+    let ini_path = game_exec_path.with_extension("ini");
+    let ini_path = if ini_path.is_file() {
+        ini_path
+    } else {
+        game_dir.join("Game.ini")
+    };
+
+    // Japanese games are usually Shift-JIS, we only care about the ASCII parts
+    let contents = String::from_utf8_lossy(
+        &std::fs::read(&ini_path)
+            .map_err(|e| stable_eyre::eyre::eyre!("Could not read {:?}: {}", ini_path, e))?,
+    )
+    .to_string();
+    let ini = parse_ini(&contents);
+    tracing::debug!(?ini_path, ?ini, "Parsed Game.ini");
+
+    let archive = ["Game.rgssad", "Game.rgss2a", "Game.rgss3a"]
+        .iter()
+        .map(|a| game_dir.join(a))
+        .find(|a| a.is_file());
+
+    let rgss_version = rgss_version(&ini)
+        .or_else(|| {
+            archive
+                .as_ref()
+                .and_then(|a| a.extension())
+                .and_then(|ext| match ext.to_str()? {
+                    "rgssad" => Some(1),
+                    "rgss2a" => Some(2),
+                    "rgss3a" => Some(3),
+                    _ => None,
+                })
+        })
+        .ok_or_else(|| {
+            stable_eyre::eyre::eyre!("Could not determine the RGSS version from {:?}", ini_path)
+        })?;
+
+    // XP uses RTP1..RTP3, VX and VX Ace use a single RTP key
+    let rtps = ["rtp", "rtp1", "rtp2", "rtp3"]
+        .iter()
+        .filter_map(|key| ini.get(*key))
+        .filter(|rtp| !rtp.is_empty())
+        .cloned()
+        .collect();
+
+    Ok(RgssGame {
+        game_dir: game_dir.to_path_buf(),
+        rgss_version,
+        title: ini.get("title").cloned(),
+        archive,
+        rtps,
+    })
+}
+
+/// Find the soundfont used for MIDI playback, from the config or the system
+pub fn find_soundfont(configured: Option<&str>) -> Option<PathBuf> {
+    if let Some(soundfont) = configured {
+        return Some(PathBuf::from(soundfont));
+    }
+    SYSTEM_SOUNDFONTS
+        .iter()
+        .map(PathBuf::from)
+        .find(|p| p.is_file())
+}
+
+/// Generate the mkxp-z config for a game
+///
+/// `rtp_paths` maps RTP names from `Game.ini` to their install directories.
+pub fn generate_config(
+    game: &RgssGame,
+    rtp_paths: &BTreeMap<String, String>,
+    soundfont: Option<&Path>,
+) -> serde_json::Value {
+    let rtps: Vec<&String> = game
+        .rtps
+        .iter()
+        .filter_map(|rtp| {
+            let path = rtp_paths.get(rtp);
+            if path.is_none() {
+                tracing::warn!(rtp, "Game uses an RTP that isn't configured in mkxp_rtps");
+            }
+            path
+        })
+        .collect();
+
+    let mut config = serde_json::json!({
+        "gameFolder": game.game_dir,
+        "rgssVersion": game.rgss_version,
+        "RTP": rtps,
+    });
+    if let Some(title) = &game.title {
+        config["windowTitle"] = title.clone().into();
+    }
+    match soundfont {
+        Some(soundfont) => config["midiSoundFont"] = soundfont.display().to_string().into(),
+        None => tracing::warn!("No soundfont found, MIDI music will not play"),
+    }
+    config
+}
+
+/// Where the generated `mkxp.json` goes in the compat data directory
+///
+/// mkxp-z reads `mkxp.json` from its working directory, so the game is launched from there.
+pub fn config_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("mkxp")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_vx_ace_game() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let game_dir = temp_dir.path();
+        let exe = game_dir.join("Game.exe");
+        std::fs::write(&exe, b"MZ").unwrap();
+        std::fs::write(game_dir.join("Game.rgss3a"), b"RGSSAD").unwrap();
+        std::fs::write(
+            game_dir.join("Game.ini"),
+            "[Game]\r\nRTP=RPGVXAce\r\nLibrary=System\\RGSS301.dll\r\nScripts=Data\\Scripts.rvdata2\r\nTitle=My Game\r\n",
+        )
+        .unwrap();
+
+        let game = detect(&exe).unwrap();
+        assert_eq!(
+            game,
+            RgssGame {
+                game_dir: game_dir.to_path_buf(),
+                rgss_version: 3,
+                title: Some("My Game".to_string()),
+                archive: Some(game_dir.join("Game.rgss3a")),
+                rtps: vec!["RPGVXAce".to_string()],
+            }
+        );
+
+        let rtp_paths = [("RPGVXAce".to_string(), "/opt/rtp/RPGVXAce".to_string())].into();
+        let config = generate_config(&game, &rtp_paths, Some(Path::new("/sf/gm.sf2")));
+        assert_eq!(config["rgssVersion"], 3);
+        assert_eq!(config["RTP"], serde_json::json!(["/opt/rtp/RPGVXAce"]));
+        assert_eq!(config["midiSoundFont"], "/sf/gm.sf2");
+        assert_eq!(config["windowTitle"], "My Game");
+    }
+
+    #[test]
+    fn test_rgss_version_from_scripts() {
+        let ini = parse_ini("[Game]\nLibrary=\nScripts=Data\\Scripts.rxdata\nRTP1=Standard\n");
+        assert_eq!(rgss_version(&ini), Some(1));
+    }
+}
@@ -0,0 +1,230 @@
+//! FNA/XNA and MonoGame support
+//!
+//! Works like MonoKickstart: the game's main assembly is run with the host `mono`, with Boson
+//! supplying FNA (and the `Microsoft.Xna.Framework` forwarding stubs) plus its native libraries
+//! from `lib/fna`, and a generated Mono config mapping the Windows native library names.
+use std::path::{Path, PathBuf};
+
+use stable_eyre::Result;
+
+use crate::pe;
+
+/// The game framework a .NET game was built with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framework {
+    /// Microsoft XNA, remapped to FNA
+    Xna,
+    /// FNA, shipped by the game
+    Fna,
+    /// MonoGame, shipped by the game
+    MonoGame,
+    /// Not a known game framework, the assembly is run as-is
+    Unknown,
+}
+
+/// Native library names used by FNA and MonoGame on Windows, and their Linux counterparts
+const DLLMAPS: [(&str, &str); 10] = [
+    ("SDL2", "libSDL2-2.0.so.0"),
+    ("SDL2.dll", "libSDL2-2.0.so.0"),
+    ("FNA3D", "libFNA3D.so.0"),
+    ("FNA3D.dll", "libFNA3D.so.0"),
+    ("FAudio", "libFAudio.so.0"),
+    ("FAudio.dll", "libFAudio.so.0"),
+    ("libtheorafile", "libtheorafile.so"),
+    ("libtheorafile.dll", "libtheorafile.so"),
+    ("soft_oal.dll", "libopenal.so.1"),
+    ("SDL2_image.dll", "libSDL2_image-2.0.so.0"),
+];
+
+/// Native libraries FNA needs, for any game run on it
+const FNA_NATIVE_LIBRARIES: [&str; 3] = ["libSDL2-2.0.so.0", "libFNA3D.so.0", "libFAudio.so.0"];
+
+/// FNA itself and the FNA.NetStub assemblies, which forward the XNA assemblies XNA games
+/// reference to FNA
+const FNA_XNA_ASSEMBLIES: [&str; 11] = [
+    "FNA.dll",
+    "Microsoft.Xna.Framework.dll",
+    "Microsoft.Xna.Framework.Avatar.dll",
+    "Microsoft.Xna.Framework.Game.dll",
+    "Microsoft.Xna.Framework.GamerServices.dll",
+    "Microsoft.Xna.Framework.Graphics.dll",
+    "Microsoft.Xna.Framework.Input.Touch.dll",
+    "Microsoft.Xna.Framework.Net.dll",
+    "Microsoft.Xna.Framework.Storage.dll",
+    "Microsoft.Xna.Framework.Video.dll",
+    "Microsoft.Xna.Framework.Xact.dll",
+];
+
+/// Places the host Mono config usually lives, which our generated config extends
+const SYSTEM_MONO_CONFIGS: [&str; 3] = [
+    "/etc/mono/config",
+    "/usr/etc/mono/config",
+    "/usr/local/etc/mono/config",
+];
+
+/// A detected .NET game
+#[derive(Debug, Clone)]
+pub struct MonoGame {
+    /// The main assembly, run with `mono`
+    pub assembly: PathBuf,
+    pub framework: Framework,
+}
+
+/// Check if a file is a .NET assembly, i.e. a PE importing `mscoree.dll`
+fn is_dotnet_assembly(path: &Path) -> bool {
+    let mut magic = [0u8; 2];
+    let is_pe = std::fs::File::open(path)
+        .and_then(|mut f| std::io::Read::read_exact(&mut f, &mut magic))
+        .is_ok()
+        && magic == *b"MZ";
+    is_pe && (pe::contains(path, b"mscoree.dll") || pe::contains(path, b"_CorExeMain"))
+}
+
+/// Detect the game framework and main assembly
+///
+/// FNA and MonoGame games ship their framework assembly next to the executable,
+/// XNA games reference `Microsoft.Xna.Framework` from the GAC instead.
+pub fn detect(game_exec_path: &Path) -> Result<MonoGame> {
+    if !is_dotnet_assembly(game_exec_path) {
+        return Err(stable_eyre::eyre::eyre!(
+            "{:?} is not a .NET assembly",
+            game_exec_path
+        ));
+    }
+
+    let game_dir = game_exec_path.parent().unwrap_or(Path::new("."));
+    let framework = if game_dir.join("FNA.dll").is_file() {
+        Framework::Fna
+    } else if game_dir.join("MonoGame.Framework.dll").is_file() {
+        Framework::MonoGame
+    } else if pe::contains(game_exec_path, b"Microsoft.Xna.Framework") {
+        Framework::Xna
+    } else {
+        Framework::Unknown
+    };
+
+    Ok(MonoGame {
+        assembly: game_exec_path.to_path_buf(),
+        framework,
+    })
+}
+
+/// Generate a Mono config with dllmaps for the FNA native libraries
+///
+/// Mono only loads a single config file when one is passed with `--config`,
+/// so the host config is included to keep its own mappings intact.
+///
+/// This only remaps native libraries. The managed XNA assemblies are remapped to FNA by the
+/// FNA.NetStub assemblies in Boson's FNA directory, which is put on `MONO_PATH`.
+pub fn generate_config(system_config: Option<&str>) -> String {
+    let dllmaps = DLLMAPS
+        .iter()
+        .map(|(dll, target)| {
+            format!("\t<dllmap dll=\"{dll}\" os=\"linux\" target=\"{target}\"/>\n")
+        })
+        .collect::<String>();
+
+    match system_config.and_then(|c| c.rfind("</configuration>").map(|i| (c, i))) {
+        Some((config, index)) => {
+            format!("{}{}{}", &config[..index], dllmaps, &config[index..])
+        }
+        None => format!("<configuration>\n{dllmaps}</configuration>\n"),
+    }
+}
+
+/// Generate the Mono config for this host, see [`generate_config`]
+pub fn host_config() -> String {
+    let system_config = SYSTEM_MONO_CONFIGS
+        .iter()
+        .find_map(|p| std::fs::read_to_string(p).ok());
+    if system_config.is_none() {
+        tracing::warn!("Could not find the host Mono config, only FNA mappings will be available");
+    }
+    generate_config(system_config.as_deref())
+}
+
+/// Where the generated Mono config goes in the compat data directory
+pub fn config_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("mono").join("config")
+}
+
+/// Files the game needs from Boson's FNA directory that are missing
+pub fn missing_fna_files(fna_dir: &Path, framework: Framework) -> Vec<&'static str> {
+    let assemblies: &[&str] = if framework == Framework::Xna {
+        &FNA_XNA_ASSEMBLIES
+    } else {
+        &[]
+    };
+    FNA_NATIVE_LIBRARIES
+        .iter()
+        .chain(assemblies)
+        .copied()
+        .filter(|file| !fna_dir.join(file).exists())
+        .collect()
+}
+
+/// Check that Boson's FNA directory has what the game needs, warning about anything missing
+pub fn check_fna_dir(fna_dir: &Path, framework: Framework) {
+    let missing = missing_fna_files(fna_dir, framework);
+    if !missing.is_empty() {
+        tracing::warn!(
+            ?fna_dir,
+            ?missing,
+            "Boson's FNA libraries are incomplete, the game will likely fail to load"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_framework() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let exe = temp_dir.path().join("Game.exe");
+        std::fs::write(
+            &exe,
+            b"MZ\x90\x00 ... mscoree.dll ... Microsoft.Xna.Framework.Game ...",
+        )
+        .unwrap();
+        assert_eq!(detect(&exe).unwrap().framework, Framework::Xna);
+
+        std::fs::write(temp_dir.path().join("FNA.dll"), b"MZ").unwrap();
+        assert_eq!(detect(&exe).unwrap().framework, Framework::Fna);
+
+        let native = temp_dir.path().join("Native.exe");
+        std::fs::write(&native, b"MZ\x90\x00 kernel32.dll").unwrap();
+        assert!(detect(&native).is_err());
+    }
+
+    #[test]
+    fn test_generate_config() {
+        let system = "<configuration>\n\t<dllmap dll=\"libc\" target=\"libc.so.6\" os=\"!windows\"/>\n</configuration>\n";
+        let config = generate_config(Some(system));
+        assert!(config.starts_with("<configuration>\n\t<dllmap dll=\"libc\""));
+        assert!(config.contains("<dllmap dll=\"FNA3D\" os=\"linux\" target=\"libFNA3D.so.0\"/>"));
+        assert!(config.trim_end().ends_with("</configuration>"));
+
+        let config = generate_config(None);
+        assert!(config.contains("<dllmap dll=\"SDL2\" os=\"linux\" target=\"libSDL2-2.0.so.0\"/>"));
+    }
+
+    #[test]
+    fn test_missing_fna_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for file in FNA_NATIVE_LIBRARIES.iter().chain(&FNA_XNA_ASSEMBLIES) {
+            std::fs::write(temp_dir.path().join(file), b"").unwrap();
+        }
+        assert!(missing_fna_files(temp_dir.path(), Framework::Xna).is_empty());
+
+        // A partial stub set
+        std::fs::remove_file(temp_dir.path().join("Microsoft.Xna.Framework.Xact.dll")).unwrap();
+        assert_eq!(
+            missing_fna_files(temp_dir.path(), Framework::Xna),
+            vec!["Microsoft.Xna.Framework.Xact.dll"]
+        );
+        // MonoGame games bring their own framework
+        assert!(missing_fna_files(temp_dir.path(), Framework::MonoGame).is_empty());
+    }
+}
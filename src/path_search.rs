@@ -1,6 +1,7 @@
 //! Path Searching module
 //!
-//! This module is a helper to quickly find the path to the Electron app's ASAR file by looking for them in common locations.
+//! This module is a helper to find the path to the Electron app's ASAR file (or unpacked app) by searching the game directory.
+//! It does the same for NW.js apps (`package.nw` or a loose `package.json`) and HTML5 web exports.
 //!
 //! It also supports checking the environment variable `BOSON_LOAD_PATH` for a custom path.
 use std::{
@@ -8,6 +9,10 @@ use std::{
     process,
 };
 
+use stable_eyre::Result;
+
+use crate::asar;
+
 pub fn global_config_path() -> Option<PathBuf> {
     if let Some(config_dir) = dirs::config_dir() {
         let cfg = config_dir.join("boson.toml");
@@ -21,6 +26,11 @@ pub fn global_config_path() -> Option<PathBuf> {
     }
 }
 
+/// The user's config directory, `~/.config/boson.d`
+pub fn user_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|f| f.join("boson.d"))
+}
+
 /// Returns a list of load paths, from the factory Boson database
 /// + any additional user configured paths
 ///
@@ -30,8 +40,7 @@ pub fn global_config_path() -> Option<PathBuf> {
 pub fn config_load_paths() -> Vec<PathBuf> {
     let mut paths = Vec::new();
 
-    let userconfig_dir = dirs::config_dir().and_then(|f| Some(f.join("boson.d")));
-    if let Some(dir) = userconfig_dir {
+    if let Some(dir) = user_config_dir() {
         paths.push(dir);
     }
 
@@ -57,96 +66,341 @@ pub fn env_boson_load_path() -> Option<String> {
     std::env::var("BOSON_LOAD_PATH").ok()
 }
 
-pub fn get_game_path(path: &Path) -> PathBuf {
+pub fn get_game_path(path: &Path) -> Result<PathBuf> {
     // remove file name from path
-    if path.is_file() {
-        path.parent().unwrap().canonicalize().unwrap().to_path_buf()
+    let dir = if path.is_file() {
+        path.parent().unwrap_or(Path::new("."))
     } else {
-        path.canonicalize().unwrap().to_path_buf()
+        path
+    };
+    dir.canonicalize()
+        .map_err(|e| stable_eyre::eyre::eyre!("Could not resolve game path {:?}: {}", dir, e))
+}
+
+/// Split a Windows path into its drive letter and the rest of the path,
+/// e.g. `C:\users\steamuser` into `('c', "users/steamuser")`
+fn split_windows_path(path: &str) -> Option<(char, String)> {
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    if chars.next() != Some(':') {
+        return None;
     }
+    let rest = chars.as_str().replace('\\', "/");
+    Some((
+        drive.to_ascii_lowercase(),
+        rest.trim_start_matches('/').to_string(),
+    ))
+}
+
+/// Translate a Windows path (as a Proton game would see it) into a host path
+///
+/// `Z:` is the host root, `C:` is the prefix's `drive_c`, other drives are resolved through
+/// the prefix's `dosdevices` links. Host paths are returned as-is.
+pub fn windows_to_native(path: &str, prefix: Option<&Path>) -> Result<PathBuf> {
+    let Some((drive, rest)) = split_windows_path(path) else {
+        return Ok(PathBuf::from(path));
+    };
+
+    let root = match (drive, prefix) {
+        ('z', _) => PathBuf::from("/"),
+        ('c', Some(prefix)) => prefix.join("drive_c"),
+        (drive, Some(prefix)) => prefix.join("dosdevices").join(format!("{drive}:")),
+        (drive, None) => {
+            return Err(stable_eyre::eyre::eyre!(
+                "Cannot translate {}: drive {}: needs STEAM_COMPAT_DATA_PATH to be set",
+                path,
+                drive.to_ascii_uppercase()
+            ))
+        }
+    };
+    Ok(root.join(rest))
+}
+
+/// Translate a host path into a Windows path, the reverse of `windows_to_native()`
+///
+/// Paths inside the prefix's `drive_c` become `C:` paths, anything else is under `Z:`.
+pub fn native_to_windows(path: &Path, prefix: Option<&Path>) -> Result<String> {
+    let path = if path.is_relative() {
+        std::env::current_dir()?.join(path)
+    } else {
+        path.to_path_buf()
+    };
+
+    let (drive, rest) = match prefix.and_then(|p| path.strip_prefix(p.join("drive_c")).ok()) {
+        Some(rest) => ('C', rest.to_path_buf()),
+        None => ('Z', path.strip_prefix("/").unwrap_or(&path).to_path_buf()),
+    };
+    Ok(format!(
+        "{}:\\{}",
+        drive,
+        rest.to_string_lossy().replace('/', "\\")
+    ))
 }
 
 pub fn env_electron_path() -> String {
     std::env::var("ELECTRON_PATH").unwrap_or_else(|_| "electron".to_string())
 }
 
-// This function scans for a package.json file in the game directory
-// Does nothing for now except logging
+/// Find an executable by name in `$PATH`
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|p| p.is_file())
+}
 
-fn package_json_scan(path: &Path) {
-    use serde_json::Value;
-    use std::fs::File;
-    use std::io::Read;
-
-    // if is file
-    if path.is_file() {
-        tracing::info!("Path is a file, ignoring.");
-        return;
-    }
-
-    let package_json = path.join("package.json");
-    if package_json.exists() {
-        tracing::info!("Found package.json at {:?}", package_json);
-
-        // Try to open the file
-        let mut file = match File::open(&package_json) {
-            Ok(file) => file,
-            Err(e) => {
-                tracing::warn!("Failed to open package.json: {:?}", e);
-                return;
-            }
-        };
+pub fn env_nwjs_path() -> String {
+    std::env::var("NWJS_PATH").unwrap_or_else(|_| "nw".to_string())
+}
+
+/// What we learned about an Electron/NW.js app from its `package.json`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppPackage {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// The entry point, resolved to a file that exists in the app
+    pub main: String,
+    /// Native modules in the app that are Windows binaries, and won't load on Linux
+    pub windows_native_modules: Vec<String>,
+}
+
+/// The files of an app, either an unpacked directory or an archive
+enum AppSource {
+    Dir(PathBuf),
+    Asar(asar::Archive),
+    Zip(PathBuf),
+}
 
-        let mut contents = String::new();
-        if let Err(e) = file.read_to_string(&mut contents) {
-            tracing::warn!("Failed to read package.json: {:?}", e);
-            return;
+impl AppSource {
+    fn open(path: &Path) -> Result<Self, String> {
+        if path.is_dir() {
+            return Ok(Self::Dir(path.to_path_buf()));
+        }
+        match asar::Archive::open(path) {
+            Ok(archive) => Ok(Self::Asar(archive)),
+            // package.nw archives are plain zips
+            Err(e) => match std::fs::File::open(path).map(zip::ZipArchive::new) {
+                Ok(Ok(_)) => Ok(Self::Zip(path.to_path_buf())),
+                _ => Err(format!("not a directory, ASAR or zip archive ({e})")),
+            },
         }
+    }
+
+    fn read(&self, file: &str) -> Option<Vec<u8>> {
+        self.read_head(file, u64::MAX)
+    }
 
-        let v: Value = match serde_json::from_str(&contents) {
-            Ok(v) => v,
-            Err(e) => {
-                tracing::warn!("Failed to parse package.json: {:?}", e);
-                return;
+    /// Read at most the first `len` bytes of a file
+    fn read_head(&self, file: &str, len: u64) -> Option<Vec<u8>> {
+        use std::io::Read;
+        let mut data = Vec::new();
+        match self {
+            Self::Dir(dir) => {
+                std::fs::File::open(dir.join(file))
+                    .ok()?
+                    .take(len)
+                    .read_to_end(&mut data)
+                    .ok()?;
+            }
+            Self::Asar(archive) => return archive.read_file_head(file, len).ok(),
+            Self::Zip(path) => {
+                let mut archive = zip::ZipArchive::new(std::fs::File::open(path).ok()?).ok()?;
+                archive
+                    .by_name(file)
+                    .ok()?
+                    .take(len)
+                    .read_to_end(&mut data)
+                    .ok()?;
             }
-        };
+        }
+        Some(data)
+    }
 
-        // Check if it's a valid Electron package
-        if v.get("main").is_none() {
-            tracing::warn!("package.json does not specify a main script, it may not be a valid Electron package.");
-        } else {
-            tracing::info!("Validated package.json as an Electron package");
+    fn files(&self) -> Vec<String> {
+        match self {
+            Self::Dir(dir) => jwalk::WalkDir::new(dir)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| !e.file_type().is_dir())
+                .filter_map(|e| {
+                    let path = e.path();
+                    let relative = path.strip_prefix(dir).ok()?;
+                    Some(relative.to_string_lossy().to_string())
+                })
+                .collect(),
+            Self::Asar(archive) => archive.files(),
+            Self::Zip(path) => std::fs::File::open(path)
+                .ok()
+                .and_then(|f| zip::ZipArchive::new(f).ok())
+                .map(|a| a.file_names().map(str::to_string).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Read and validate an app's `package.json`, from an unpacked directory or straight from
+/// an `app.asar` (or `package.nw`) archive without extracting it
+///
+/// Returns why the app was rejected on failure.
+pub fn scan_app(path: &Path) -> Result<AppPackage, String> {
+    let source = AppSource::open(path)?;
+    let package_json = source
+        .read("package.json")
+        .ok_or_else(|| "no package.json".to_string())?;
+    let package: serde_json::Value =
+        serde_json::from_slice(&package_json).map_err(|e| format!("invalid package.json ({e})"))?;
+
+    let files: std::collections::BTreeSet<String> = source.files().into_iter().collect();
+
+    // Same resolution as Node: the file itself, with `.js`, or `index.js` in the directory
+    let main = package
+        .get("main")
+        .and_then(|m| m.as_str())
+        .unwrap_or("index.js")
+        .trim_start_matches("./")
+        .trim_end_matches('/');
+    let main = [
+        main.to_string(),
+        format!("{main}.js"),
+        format!("{main}/index.js"),
+    ]
+    .into_iter()
+    .find(|candidate| files.contains(candidate))
+    .ok_or_else(|| format!("main script {main:?} does not exist"))?;
+
+    let windows_native_modules = files
+        .iter()
+        .filter(|f| f.ends_with(".node"))
+        .filter(|f| {
+            source
+                .read_head(f, 2)
+                .is_some_and(|data| data.starts_with(b"MZ"))
+        })
+        .cloned()
+        .collect();
+
+    let string = |key: &str| {
+        package
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    Ok(AppPackage {
+        name: string("productName").or_else(|| string("name")),
+        version: string("version"),
+        main,
+        windows_native_modules,
+    })
+}
+
+/// Validate the app's `package.json` and log what we found
+fn package_json_scan(path: &Path) {
+    match scan_app(path) {
+        Ok(app) => {
+            tracing::info!(
+                name = ?app.name,
+                version = ?app.version,
+                main = %app.main,
+                "Validated package.json at {:?}",
+                path
+            );
+            if !app.windows_native_modules.is_empty() {
+                tracing::warn!(
+                    "App ships Windows-only native modules, these will fail to load: {:?}",
+                    app.windows_native_modules
+                );
+            }
+        }
+        Err(reason) => {
+            tracing::warn!(
+                "Could not validate app at {:?}: {}. This may not be the game directory.",
+                path,
+                reason
+            );
         }
-    } else {
-        tracing::warn!(
-            "Could not find package.json in game directory. This may not be the game directory."
-        );
     }
 }
 
-fn _compat_data_path() -> Option<String> {
+/// Boson's per-game data directory, for generated configs and caches
+///
+/// This is `$STEAM_COMPAT_DATA_PATH/boson`, or `~/.cache/boson/<app_id>`
+/// when not launched by Steam
+pub fn compat_data_path(app_id: u32) -> PathBuf {
     std::env::var("STEAM_COMPAT_DATA_PATH")
         .ok()
         .map(|s| PathBuf::from(s).join("boson"))
-        .map(|p| p.to_str().unwrap().to_string())
+        .unwrap_or_else(|| {
+            dirs::cache_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("boson")
+                .join(app_id.to_string())
+        })
+}
+
+/// Get the game's root directory, from `STEAM_COMPAT_INSTALL_PATH` if set
+/// or the directory containing the game executable otherwise
+fn game_root_path(game_exec_path: &Path) -> PathBuf {
+    if let Ok(path) = std::env::var("STEAM_COMPAT_INSTALL_PATH") {
+        tracing::info!("STEAM_COMPAT_INSTALL_PATH found: {:?}", path);
+        path.into()
+    }
+    // If the game path is not provided, use the game executable path
+    else {
+        get_game_path(game_exec_path).unwrap_or_else(|e| {
+            tracing::warn!("{:?}", e);
+            game_exec_path.to_path_buf()
+        })
+    }
+}
+
+/// How deep to look for Electron apps in the game directory
+const APP_SEARCH_DEPTH: usize = 6;
+
+/// Find every possible Electron app in the game directory, best candidates first
+///
+/// Candidates are directories with a `package.json` and `.asar` archives, ranked with
+/// unpacked directories before archives (games may be unpacked for development or modding),
+/// then shallower before deeper. `node_modules` is skipped entirely.
+pub fn find_app_candidates(game_path: &Path) -> Vec<PathBuf> {
+    let mut candidates: Vec<(bool, usize, PathBuf)> = jwalk::WalkDir::new(game_path)
+        .max_depth(APP_SEARCH_DEPTH)
+        .sort(true)
+        .process_read_dir(|_, _, _, children| {
+            children.retain(|entry| {
+                entry
+                    .as_ref()
+                    .map(|e| e.file_name() != "node_modules")
+                    .unwrap_or(false)
+            });
+        })
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let is_asar =
+                e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "asar");
+            if is_asar {
+                Some((true, e.depth(), e.path()))
+            } else if e.file_type().is_file() && e.file_name() == "package.json" {
+                Some((false, e.depth(), e.parent_path().to_path_buf()))
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort();
+    candidates.into_iter().map(|(_, _, path)| path).collect()
 }
 
 /// Get ASAR path
 ///
 /// Accepts a game root directory, usually from `get_game_path()`
-/// and returns the path to the ASAR
+/// and returns the path to the ASAR, or the unpacked app directory
+///
+/// The first candidate from `find_app_candidates()` whose `package.json` has a `main`
+/// pointing to an existing script is used.
 #[tracing::instrument]
 pub fn get_asar_path(game_exec_path: &Path) -> Option<PathBuf> {
-    let game_path = {
-        if let Ok(path) = std::env::var("STEAM_COMPAT_INSTALL_PATH") {
-            tracing::info!("STEAM_COMPAT_INSTALL_PATH found: {:?}", path);
-            path.into()
-        }
-        // If the game path is not provided, use the game executable path
-        else {
-            get_game_path(game_exec_path)
-        }
-    };
+    let game_path = game_root_path(game_exec_path);
 
     tracing::trace!("Game path: {:?}", game_path);
     // First check if there's an override in the environment
@@ -154,15 +408,6 @@ pub fn get_asar_path(game_exec_path: &Path) -> Option<PathBuf> {
         return Some(game_path.join(path));
     }
 
-    // ASAR paths priority
-    //
-    // Unpacked folders are prioritized over ASAR files
-    // as games may be unpacked for development or modding.
-    //
-    // todo: walk through every directory in the actual game path (except node_modules) and find a package.json with "main" pointing to the JS file
-    // if found, return that directory as the game path
-    // else find the ASAR archive
-
     const ASAR_PATHS: [&str; 4] = [
         "app.asar",
         "resources/app.asar.unpacked",
@@ -180,19 +425,252 @@ pub fn get_asar_path(game_exec_path: &Path) -> Option<PathBuf> {
         return Some(game_path);
     }
 
-    for path in ASAR_PATHS.iter() {
-        let asar_path = game_path.join(path);
-        tracing::trace!("Checking path: {:?}", asar_path);
-        if asar_path.exists() {
-            if asar_path.is_dir() {
-                tracing::info!("Found unpacked ASAR at {:?}", asar_path);
+    for candidate in find_app_candidates(&game_path) {
+        tracing::trace!("Checking path: {:?}", candidate);
+        // Electron's own fallback app, shipped next to the game's app.asar
+        if candidate
+            .file_name()
+            .is_some_and(|name| name == "default_app.asar")
+        {
+            tracing::info!("Rejected {:?}: Electron's default app", candidate);
+            continue;
+        }
+
+        match scan_app(&candidate) {
+            Ok(app) => {
+                if candidate.is_dir() {
+                    tracing::info!("Found unpacked ASAR at {:?}", candidate);
+                } else {
+                    tracing::info!("Found ASAR at {:?}", candidate);
+                }
+                tracing::info!(
+                    name = ?app.name,
+                    version = ?app.version,
+                    main = %app.main,
+                    "Validated package.json"
+                );
+                if !app.windows_native_modules.is_empty() {
+                    tracing::warn!(
+                        "App ships Windows-only native modules, these will fail to load: {:?}",
+                        app.windows_native_modules
+                    );
+                }
+                return Some(candidate);
+            }
+            Err(reason) => tracing::info!("Rejected {:?}: {}", candidate, reason),
+        }
+    }
+
+    None
+}
+
+/// Get NW.js app path
+///
+/// Accepts the game executable path and returns the path to the NW.js app,
+/// either a `package.nw` archive/directory, or the directory containing a loose `package.json`
+/// (usually right next to `nw.exe`, as RPG Maker MV/MZ games ship it)
+#[tracing::instrument]
+pub fn get_nwjs_path(game_exec_path: &Path) -> Option<PathBuf> {
+    let game_path = game_root_path(game_exec_path);
+
+    tracing::trace!("Game path: {:?}", game_path);
+    if let Some(path) = env_boson_load_path() {
+        return Some(game_path.join(path));
+    }
+
+    // NW.js app paths priority, packaged apps first
+    // then loose package.json files next to the executable
+    const NWJS_PATHS: [&str; 3] = ["package.nw", "resources/package.nw", "resources/app.nw"];
+
+    for path in NWJS_PATHS.iter() {
+        let nw_path = game_path.join(path);
+        tracing::trace!("Checking path: {:?}", nw_path);
+        if nw_path.exists() {
+            if nw_path.is_dir() {
+                tracing::info!("Found unpacked NW.js app at {:?}", nw_path);
+                package_json_scan(&nw_path);
             } else {
-                tracing::info!("Found ASAR at {:?}", asar_path);
+                tracing::info!("Found NW.js app package at {:?}", nw_path);
             }
-            package_json_scan(&asar_path);
-            return Some(asar_path);
+            return Some(nw_path);
         }
     }
 
+    if game_path.join("package.json").is_file() {
+        tracing::info!("Found loose NW.js app at {:?}", game_path);
+        package_json_scan(&game_path);
+        return Some(game_path);
+    }
+
     None
 }
+
+/// Get the web root of an HTML5 web export
+///
+/// Accepts the game executable path and returns the shallowest directory containing
+/// an `index.html` along with other assets, skipping `node_modules`
+#[tracing::instrument]
+pub fn get_web_root(game_exec_path: &Path) -> Option<PathBuf> {
+    let game_path = game_root_path(game_exec_path);
+
+    tracing::trace!("Game path: {:?}", game_path);
+    if let Some(path) = env_boson_load_path() {
+        return Some(game_path.join(path));
+    }
+
+    let mut candidates: Vec<(usize, PathBuf)> = jwalk::WalkDir::new(&game_path)
+        .max_depth(4)
+        .process_read_dir(|_, _, _, children| {
+            children.retain(|entry| {
+                entry
+                    .as_ref()
+                    .map(|e| e.file_name() != "node_modules")
+                    .unwrap_or(false)
+            });
+        })
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.file_name() == "index.html")
+        .map(|e| (e.depth(), e.parent_path().to_path_buf()))
+        .collect();
+    candidates.sort();
+
+    for (_, dir) in candidates {
+        // A lone index.html is probably a readme or a redirect, not the game
+        let has_assets = std::fs::read_dir(&dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .any(|e| e.file_name() != "index.html")
+            })
+            .unwrap_or(false);
+        if has_assets {
+            tracing::info!("Found web root at {:?}", dir);
+            return Some(dir);
+        }
+        tracing::debug!("Skipping {:?}, index.html has no assets next to it", dir);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_path_translation() {
+        let prefix = Path::new("/steam/compatdata/123/pfx");
+        let translate = |path: &str| windows_to_native(path, Some(prefix)).unwrap();
+        assert_eq!(
+            translate("C:\\users\\steamuser\\Saved Games"),
+            prefix.join("drive_c/users/steamuser/Saved Games")
+        );
+        assert_eq!(
+            translate("z:\\home\\user\\game"),
+            PathBuf::from("/home/user/game")
+        );
+        assert_eq!(translate("D:/data"), prefix.join("dosdevices/d:/data"));
+        assert_eq!(translate("/home/user"), PathBuf::from("/home/user"));
+        assert!(windows_to_native("C:\\windows", None).is_err());
+
+        assert_eq!(
+            native_to_windows(&prefix.join("drive_c/users/steamuser"), Some(prefix)).unwrap(),
+            "C:\\users\\steamuser"
+        );
+        assert_eq!(
+            native_to_windows(Path::new("/home/user/game"), Some(prefix)).unwrap(),
+            "Z:\\home\\user\\game"
+        );
+    }
+
+    #[test]
+    fn test_find_app_candidates() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let game_path = temp_dir.path();
+        for dir in [
+            "bin/resources",
+            "game/app/src",
+            "tools",
+            "game/app/node_modules/dep",
+        ] {
+            std::fs::create_dir_all(game_path.join(dir)).unwrap();
+        }
+        std::fs::write(
+            game_path.join("bin/resources/app.asar"),
+            asar::tests::build_asar(&[
+                ("package.json", br#"{"main": "main.js"}"#),
+                ("main.js", b""),
+            ]),
+        )
+        .unwrap();
+        std::fs::write(
+            game_path.join("game/app/package.json"),
+            r#"{"main": "src/main.js"}"#,
+        )
+        .unwrap();
+        std::fs::write(game_path.join("game/app/src/main.js"), "").unwrap();
+        std::fs::write(
+            game_path.join("game/app/node_modules/dep/package.json"),
+            "{}",
+        )
+        .unwrap();
+        // Shallowest, but its main script doesn't exist
+        std::fs::write(
+            game_path.join("tools/package.json"),
+            r#"{"main": "cli.js"}"#,
+        )
+        .unwrap();
+
+        let candidates = find_app_candidates(game_path);
+        assert_eq!(
+            candidates,
+            vec![
+                game_path.join("tools"),
+                game_path.join("game/app"),
+                game_path.join("bin/resources/app.asar"),
+            ]
+        );
+        assert!(scan_app(&candidates[0]).is_err());
+        assert!(scan_app(&candidates[1]).is_ok());
+    }
+
+    #[test]
+    fn test_scan_app_from_asar() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let asar = temp_dir.path().join("app.asar");
+        std::fs::write(
+            &asar,
+            asar::tests::build_asar(&[
+                (
+                    "package.json",
+                    br#"{"name": "game", "productName": "Game", "version": "1.2.0", "main": "./src/main"}"#,
+                ),
+                ("src/main.js", b"require('electron')"),
+                ("node_modules/greenworks/lib/greenworks-win64.node", b"MZ\x90\x00"),
+                ("node_modules/greenworks/lib/greenworks-linux64.node", b"\x7fELF"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            scan_app(&asar).unwrap(),
+            AppPackage {
+                name: Some("Game".to_string()),
+                version: Some("1.2.0".to_string()),
+                main: "src/main.js".to_string(),
+                windows_native_modules: vec![
+                    "node_modules/greenworks/lib/greenworks-win64.node".to_string()
+                ],
+            }
+        );
+
+        let broken = temp_dir.path().join("broken.asar");
+        std::fs::write(
+            &broken,
+            asar::tests::build_asar(&[("package.json", br#"{"main": "missing.js"}"#)]),
+        )
+        .unwrap();
+        assert!(scan_app(&broken).unwrap_err().contains("does not exist"));
+    }
+}
@@ -0,0 +1,219 @@
+//! Minimal Windows PE helpers
+//!
+//! Just enough to read what Windows builds of game runtimes tell us about themselves,
+//! without pulling in a full PE parser.
+use std::{fs::File, io::Read, path::Path};
+
+/// Signature of the `VS_FIXEDFILEINFO` structure in a PE version resource
+pub const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF_04BD;
+
+/// How much of a file is read at once when scanning it
+const CHUNK_LEN: usize = 1 << 20;
+
+/// Find the first occurrence of `pattern` in a file, returning up to `len` bytes from it
+///
+/// The file is scanned in chunks as runtime executables can be hundreds of megabytes.
+pub fn scan(path: &Path, pattern: &[u8], len: usize) -> Option<Vec<u8>> {
+    let mut file = File::open(path).ok()?;
+    let mut buf = vec![0u8; CHUNK_LEN.max(len * 2)];
+    let mut filled = 0;
+    loop {
+        let read = file.read(&mut buf[filled..]).ok()?;
+        let eof = read == 0;
+        filled += read;
+
+        if let Some(index) = buf[..filled]
+            .windows(pattern.len())
+            .position(|w| w == pattern)
+        {
+            if index + len <= filled || eof {
+                return Some(buf[index..filled.min(index + len)].to_vec());
+            }
+            // The match straddles the end of the buffer, keep it and read more
+            buf.copy_within(index..filled, 0);
+            filled -= index;
+            continue;
+        }
+        if eof {
+            return None;
+        }
+
+        // Keep the last few bytes in case the pattern straddles chunks
+        let keep = filled.min(pattern.len() - 1);
+        buf.copy_within(filled - keep..filled, 0);
+        filled = keep;
+    }
+}
+
+/// Check if a file contains `pattern` anywhere, see [`scan`]
+pub fn contains(path: &Path, pattern: &[u8]) -> bool {
+    scan(path, pattern, pattern.len()).is_some()
+}
+
+/// Find every occurrence of `pattern` in a file, returning each with up to `before` bytes
+/// preceding it and `after` bytes following it
+///
+/// Like [`scan`], the file is read in chunks rather than all at once.
+pub fn scan_all(path: &Path, pattern: &[u8], before: usize, after: usize) -> Vec<Vec<u8>> {
+    let Ok(mut file) = File::open(path) else {
+        return vec![];
+    };
+    let window = before + pattern.len() + after;
+    let mut buf = vec![0u8; CHUNK_LEN.max(window * 2)];
+    let mut found = Vec::new();
+    let mut filled = 0;
+    // Matches starting before this index have already been handled
+    let mut next = 0;
+    loop {
+        let Ok(read) = file.read(&mut buf[filled..]) else {
+            return found;
+        };
+        let eof = read == 0;
+        filled += read;
+
+        while next + pattern.len() <= filled {
+            // Wait for the bytes following a match, unless the file ends first
+            if next + pattern.len() + after > filled && !eof {
+                break;
+            }
+            if buf[next..].starts_with(pattern) {
+                let end = filled.min(next + pattern.len() + after);
+                found.push(buf[next.saturating_sub(before)..end].to_vec());
+            }
+            next += 1;
+        }
+        if eof {
+            return found;
+        }
+
+        // Keep what the next matches may need, the bytes before them included
+        let start = next.saturating_sub(before);
+        buf.copy_within(start..filled, 0);
+        next -= start;
+        filled -= start;
+    }
+}
+
+/// Read the file version from the `VS_FIXEDFILEINFO` in a PE version resource
+///
+/// Returns the four version components, e.g. `[11, 4, 0, 0]`.
+pub fn file_version(path: &Path) -> Option<[u32; 4]> {
+    // signature, struct version, then the file version as two dwords
+    const INFO_LEN: usize = 16;
+    let info = scan(path, &FIXED_FILE_INFO_SIGNATURE.to_le_bytes(), INFO_LEN)?;
+    if info.len() < INFO_LEN {
+        return None;
+    }
+    let dword = |offset: usize| {
+        u32::from_le_bytes([
+            info[offset],
+            info[offset + 1],
+            info[offset + 2],
+            info[offset + 3],
+        ])
+    };
+    let (ms, ls) = (dword(8), dword(12));
+    Some([ms >> 16, ms & 0xffff, ls >> 16, ls & 0xffff])
+}
+
+/// Read a string from the `StringFileInfo` in a PE version resource, e.g. `ProductName`
+///
+/// Version strings are UTF-16: the key and its terminator, padding to a 32-bit boundary,
+/// then the value up to its terminator.
+pub fn version_string(path: &Path, key: &str) -> Option<String> {
+    const MAX_VALUE_LEN: usize = 512;
+    let pattern: Vec<u8> = key
+        .encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    let data = scan(path, &pattern, pattern.len() + MAX_VALUE_LEN)?;
+    let value: Vec<u16> = data[pattern.len()..]
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .skip_while(|&c| c == 0)
+        .take_while(|&c| c != 0)
+        .collect();
+    (!value.is_empty()).then(|| String::from_utf16_lossy(&value))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A `String` entry of a version resource, as it's laid out in the file
+    pub(crate) fn version_string_entry(key: &str, value: &str) -> Vec<u8> {
+        let utf16 = |s: &str| -> Vec<u8> {
+            s.encode_utf16()
+                .chain([0])
+                .flat_map(u16::to_le_bytes)
+                .collect()
+        };
+        // wLength, wValueLength, wType, then the key, padding and value
+        let mut entry = vec![0u8; 6];
+        entry.extend(utf16(key));
+        while !entry.len().is_multiple_of(4) {
+            entry.push(0);
+        }
+        entry.extend(utf16(value));
+        entry
+    }
+
+    #[test]
+    fn test_file_version() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let exe = temp_dir.path().join("game.exe");
+
+        // Put the signature right across the first chunk boundary
+        let mut bytes = vec![0u8; (1 << 20) - 2];
+        bytes.extend(FIXED_FILE_INFO_SIGNATURE.to_le_bytes());
+        bytes.extend(0x0001_0000u32.to_le_bytes());
+        bytes.extend(0x0008_0005u32.to_le_bytes());
+        bytes.extend(0x0005_0001u32.to_le_bytes());
+        std::fs::write(&exe, bytes).unwrap();
+        assert_eq!(file_version(&exe), Some([8, 5, 5, 1]));
+
+        std::fs::write(&exe, b"MZ no version resource").unwrap();
+        assert_eq!(file_version(&exe), None);
+        assert_eq!(version_string(&exe, "ProductName"), None);
+
+        std::fs::write(
+            &exe,
+            [
+                b"MZ".to_vec(),
+                version_string_entry("ProductName", "Electron"),
+            ]
+            .concat(),
+        )
+        .unwrap();
+        assert_eq!(
+            version_string(&exe, "ProductName"),
+            Some("Electron".to_string())
+        );
+    }
+
+    #[test]
+    fn test_scan_all() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let exe = temp_dir.path().join("game.exe");
+
+        // One match right across the first chunk boundary, one at the very end
+        let mut bytes = b"MZ\0lib/a.jar\0".to_vec();
+        bytes.resize(CHUNK_LEN - 6, 0);
+        bytes.extend(b"\0game.jar\0");
+        bytes.resize(CHUNK_LEN * 2, 0);
+        bytes.extend(b"\0last.jar");
+        std::fs::write(&exe, &bytes).unwrap();
+
+        assert_eq!(
+            scan_all(&exe, b".jar", 4, 1),
+            vec![
+                b"ib/a.jar\0".to_vec(),
+                b"game.jar\0".to_vec(),
+                b"last.jar".to_vec()
+            ]
+        );
+        assert!(contains(&exe, b"game.jar"));
+        assert!(!contains(&exe, b"other.jar"));
+    }
+}
@@ -0,0 +1,282 @@
+//! Where each setting of a game's config comes from
+//!
+//! A game's config is merged from several layers: the runtime defaults of its compat type,
+//! built-in defaults, the global config and every config file in the load paths. To show which
+//! layer set what, the layers are replayed through the same merge as [`BosonConfig::get_game_config`]
+//! and each field is attributed to the layer that last changed it.
+//!
+//! [`BosonConfig::get_game_config`]: crate::config::BosonConfig::get_game_config
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+use stable_eyre::Result;
+
+use crate::config::{CompatType, GameConfig};
+
+/// A layer of configuration
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigSource {
+    /// `GameConfig`'s own defaults
+    Default,
+    /// `CompatType::runtime_defaults` of the game's compat type
+    RuntimeDefaults(CompatType),
+    /// Boson's built-in configs for known games, `default_game_configs`
+    BuiltinGame,
+    /// The global config, `~/.config/boson.toml`
+    GlobalConfig(PathBuf),
+    /// A user config file in `~/.config/boson.d`
+    UserConfig(PathBuf),
+    /// A config file shipped in the compat tool's `data/` directory
+    CompatToolData(PathBuf),
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "built-in defaults"),
+            ConfigSource::RuntimeDefaults(compat_type) => {
+                write!(f, "runtime defaults for {compat_type:?}")
+            }
+            ConfigSource::BuiltinGame => write!(f, "built-in game config"),
+            ConfigSource::GlobalConfig(path) => write!(f, "global config {}", path.display()),
+            ConfigSource::UserConfig(path) => write!(f, "user config {}", path.display()),
+            ConfigSource::CompatToolData(path) => {
+                write!(f, "compat tool data {}", path.display())
+            }
+        }
+    }
+}
+
+impl Serialize for ConfigSource {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Where a field of the final config comes from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum FieldSource {
+    /// A single value, set by one layer
+    Value(ConfigSource),
+    /// A list, each element possibly coming from a different layer
+    Elements(Vec<ConfigSource>),
+    /// A map, each entry possibly coming from a different layer
+    Entries(BTreeMap<String, ConfigSource>),
+}
+
+/// The source of each field in the final config, unset fields are left out
+pub type Provenance = BTreeMap<String, FieldSource>;
+
+/// Attribute the changes from `before` to `after` to `source`
+fn attribute(provenance: &mut Provenance, before: &Value, after: &Value, source: &ConfigSource) {
+    let Value::Object(after) = after else {
+        return;
+    };
+    for (field, new) in after {
+        let old = before.get(field).unwrap_or(&Value::Null);
+        if old == new && provenance.contains_key(field) {
+            continue;
+        }
+        match new {
+            Value::Null => {
+                provenance.remove(field);
+            }
+            Value::Array(new) => {
+                // Lists are either extended, keeping the sources of existing elements, or replaced
+                let kept = match (old, provenance.get(field)) {
+                    (Value::Array(old), Some(FieldSource::Elements(sources)))
+                        if new.starts_with(old) =>
+                    {
+                        sources.clone()
+                    }
+                    _ => Vec::new(),
+                };
+                let mut sources = kept;
+                sources.resize(new.len(), source.clone());
+                provenance.insert(field.clone(), FieldSource::Elements(sources));
+            }
+            Value::Object(new) => {
+                let mut sources = match provenance.get(field) {
+                    Some(FieldSource::Entries(sources)) => sources.clone(),
+                    _ => BTreeMap::new(),
+                };
+                sources.retain(|key, _| new.contains_key(key));
+                for (key, value) in new {
+                    if old.get(key) != Some(value) || !sources.contains_key(key) {
+                        sources.insert(key.clone(), source.clone());
+                    }
+                }
+                provenance.insert(field.clone(), FieldSource::Entries(sources));
+            }
+            _ => {
+                provenance.insert(field.clone(), FieldSource::Value(source.clone()));
+            }
+        }
+    }
+}
+
+/// Merge layers onto a base config, tracking where each field comes from
+///
+/// `merge` must be the same merge used to build the config for the result to match it.
+pub fn trace_layers(
+    base: (ConfigSource, GameConfig),
+    layers: &[(ConfigSource, GameConfig)],
+    merge: impl Fn(&mut GameConfig, &GameConfig),
+) -> Result<(GameConfig, Provenance)> {
+    let (base_source, mut config) = base;
+    let mut provenance = Provenance::new();
+    let mut before = Value::Null;
+    let mut after = serde_json::to_value(&config)?;
+    attribute(&mut provenance, &before, &after, &base_source);
+
+    for (source, layer) in layers {
+        before = after;
+        merge(&mut config, layer);
+        after = serde_json::to_value(&config)?;
+        attribute(&mut provenance, &before, &after, source);
+    }
+    Ok((config, provenance))
+}
+
+/// Format a TOML key, quoting it if needed
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
+/// Render a config as TOML, with the source of each field in a comment
+pub fn to_annotated_toml(config: &GameConfig, provenance: &Provenance) -> Result<String> {
+    let toml::Value::Table(table) = toml::Value::try_from(config)? else {
+        return Err(stable_eyre::eyre::eyre!(
+            "Config did not serialize to a table"
+        ));
+    };
+
+    let mut out = String::new();
+    for (field, value) in &table {
+        let source = provenance.get(field);
+        match (value, source) {
+            (toml::Value::Array(values), Some(FieldSource::Elements(sources))) => {
+                if values.is_empty() {
+                    out.push_str(&format!("{field} = []\n"));
+                    continue;
+                }
+                out.push_str(&format!("{field} = [\n"));
+                for (value, source) in values.iter().zip(sources) {
+                    out.push_str(&format!("    {value}, # {source}\n"));
+                }
+                out.push_str("]\n");
+            }
+            (toml::Value::Table(entries), Some(FieldSource::Entries(sources))) => {
+                if entries.is_empty() {
+                    out.push_str(&format!("{field} = {{}}\n"));
+                }
+                // Dotted keys, so each entry gets its own line and comment
+                for (key, value) in entries {
+                    out.push_str(&format!("{field}.{} = {value}", toml_key(key)));
+                    if let Some(source) = sources.get(key) {
+                        out.push_str(&format!(" # {source}"));
+                    }
+                    out.push('\n');
+                }
+            }
+            (value, Some(FieldSource::Value(source))) => {
+                out.push_str(&format!("{field} = {value} # {source}\n"));
+            }
+            (value, _) => out.push_str(&format!("{field} = {value}\n")),
+        }
+    }
+    Ok(out)
+}
+
+/// Render a config as JSON, with the sources of its fields alongside it
+pub fn to_annotated_json(config: &GameConfig, provenance: &Provenance) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&serde_json::json!({
+        "config": config,
+        "sources": provenance,
+    }))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_layers() {
+        let global = ConfigSource::GlobalConfig(PathBuf::from("/home/user/.config/boson.toml"));
+        let user = ConfigSource::UserConfig(PathBuf::from("/home/user/.config/boson.d/game.toml"));
+        let layers = vec![
+            (
+                global.clone(),
+                GameConfig {
+                    compat_type: CompatType::Electron,
+                    wrapper_args: vec!["--common-arg".to_string()],
+                    env_vars: [("GLOBAL_VAR".to_string(), "enabled".to_string())].into(),
+                    ..Default::default()
+                },
+            ),
+            (
+                user.clone(),
+                GameConfig {
+                    compat_type: CompatType::Electron,
+                    wrapper_args: vec!["--no-sandbox".to_string()],
+                    env_vars: [("GAME_VAR".to_string(), "1".to_string())].into(),
+                    web_zoom: Some(1.5),
+                    ..Default::default()
+                },
+            ),
+        ];
+        let runtime = ConfigSource::RuntimeDefaults(CompatType::Electron);
+        let (config, provenance) = trace_layers(
+            (runtime.clone(), CompatType::Electron.runtime_defaults()),
+            &layers,
+            |base, overlay| {
+                base.wrapper_args.extend(overlay.wrapper_args.clone());
+                base.env_vars.extend(overlay.env_vars.clone());
+                if overlay.web_zoom.is_some() {
+                    base.web_zoom = overlay.web_zoom;
+                }
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            provenance["wrapper_args"],
+            FieldSource::Elements(vec![global.clone(), user.clone()])
+        );
+        assert_eq!(
+            provenance["env_vars"],
+            FieldSource::Entries(
+                [
+                    ("GAME_VAR".to_string(), user.clone()),
+                    ("GLOBAL_VAR".to_string(), global.clone())
+                ]
+                .into()
+            )
+        );
+        assert_eq!(provenance["web_zoom"], FieldSource::Value(user.clone()));
+        assert_eq!(
+            provenance["disable_steam_overlay"],
+            FieldSource::Value(runtime)
+        );
+        assert!(!provenance.contains_key("wrapper_command"));
+
+        let toml = to_annotated_toml(&config, &provenance).unwrap();
+        assert!(toml.contains(
+            "    \"--no-sandbox\", # user config /home/user/.config/boson.d/game.toml\n"
+        ));
+        assert!(toml.contains(
+            "env_vars.GLOBAL_VAR = \"enabled\" # global config /home/user/.config/boson.toml\n"
+        ));
+        assert!(toml.contains("disable_steam_overlay = true # runtime defaults for Electron\n"));
+    }
+}
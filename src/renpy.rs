@@ -0,0 +1,286 @@
+//! Ren'Py support
+//!
+//! Ren'Py games on Steam often only ship the Windows Python build, but the `game/` directory
+//! itself is portable. We run it with a host Ren'Py SDK of a matching version instead.
+use std::path::{Path, PathBuf};
+
+use stable_eyre::Result;
+
+/// Ren'Py version, without the build number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RenPyVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl std::fmt::Display for RenPyVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// A Ren'Py game (or SDK) base directory
+#[derive(Debug, Clone)]
+pub struct RenPyGame {
+    /// Directory containing `renpy/` and `game/`
+    pub base_dir: PathBuf,
+    pub version: Option<RenPyVersion>,
+    /// `config.save_directory` from the game scripts
+    pub save_directory: Option<String>,
+}
+
+/// Get the value of a Python assignment (`name = value`) from a line, if it assigns `name`
+fn python_assignment<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let (lhs, rhs) = line.split_once('=')?;
+    if lhs.trim() != name {
+        return None;
+    }
+    Some(rhs.trim())
+}
+
+fn parse_version_numbers<'a>(parts: impl Iterator<Item = &'a str>) -> Option<RenPyVersion> {
+    let mut numbers = parts.map(|p| p.trim().parse::<u32>());
+    Some(RenPyVersion {
+        major: numbers.next()?.ok()?,
+        minor: numbers.next()?.ok()?,
+        patch: numbers.next()?.ok()?,
+    })
+}
+
+/// Read the Ren'Py version from a base directory
+///
+/// Ren'Py 8 (and late 7.x) writes `version = '8.1.3.23091805'` to `renpy/vc_version.py`,
+/// older versions only have `version_tuple = (7, 4, 11, vc_version)` in `renpy/__init__.py`.
+pub fn read_version(base_dir: &Path) -> Option<RenPyVersion> {
+    let renpy_dir = base_dir.join("renpy");
+
+    if let Ok(vc_version) = std::fs::read_to_string(renpy_dir.join("vc_version.py")) {
+        let version = vc_version
+            .lines()
+            .filter_map(|line| python_assignment(line, "version"))
+            .find_map(|value| parse_version_numbers(value.trim_matches(['\'', '"']).split('.')));
+        if version.is_some() {
+            return version;
+        }
+    }
+
+    let init = std::fs::read_to_string(renpy_dir.join("__init__.py")).ok()?;
+    init.lines()
+        .filter_map(|line| python_assignment(line, "version_tuple"))
+        .find_map(|value| {
+            // `(7, 4, 11, vc_version)` or `VersionTuple(8, 1, 3, vc_version)`
+            let (_, args) = value.split_once('(')?;
+            parse_version_numbers(args.split(','))
+        })
+}
+
+/// Find `config.save_directory` in the game's scripts
+fn read_save_directory(game_dir: &Path) -> Option<String> {
+    let mut scripts: Vec<PathBuf> = std::fs::read_dir(game_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "rpy"))
+        .collect();
+    // options.rpy is where the project template puts it
+    scripts.sort_by_key(|p| !p.ends_with("options.rpy"));
+
+    scripts.iter().find_map(|script| {
+        let contents = std::fs::read_to_string(script).ok()?;
+        contents.lines().find_map(|line| {
+            let line = line.trim().trim_start_matches("define ");
+            let value = python_assignment(line, "config.save_directory")?;
+            let value = value.trim_matches(['\'', '"']);
+            (!value.is_empty() && value != "None").then(|| value.to_string())
+        })
+    })
+}
+
+/// Detect a Ren'Py game, i.e. `renpy/` and `game/` directories next to the executable
+pub fn detect(base_dir: &Path) -> Option<RenPyGame> {
+    if !base_dir.join("renpy").is_dir() || !base_dir.join("game").is_dir() {
+        return None;
+    }
+
+    Some(RenPyGame {
+        base_dir: base_dir.to_path_buf(),
+        version: read_version(base_dir),
+        save_directory: read_save_directory(&base_dir.join("game")),
+    })
+}
+
+/// Pick the SDK best matching the game's version from the configured SDK paths
+///
+/// Preference order:
+/// - The exact same version
+/// - The same major.minor version, newest patch first
+/// - The same major version, newest first, as long as it's not older than the game
+///
+/// Ren'Py 7 and 8 are never mixed, as they run on Python 2 and 3 respectively.
+pub fn select_sdk(version: Option<RenPyVersion>, sdks: &[PathBuf]) -> Result<PathBuf> {
+    let mut available: Vec<(PathBuf, RenPyVersion)> = sdks
+        .iter()
+        .filter_map(|sdk| {
+            if !sdk.join("renpy.sh").is_file() {
+                tracing::warn!(?sdk, "Configured Ren'Py SDK has no renpy.sh, skipping");
+                return None;
+            }
+            match read_version(sdk) {
+                Some(v) => Some((sdk.clone(), v)),
+                None => {
+                    tracing::warn!(?sdk, "Could not read Ren'Py SDK version, skipping");
+                    None
+                }
+            }
+        })
+        .collect();
+    // newest first
+    available.sort_by_key(|(_, v)| std::cmp::Reverse(*v));
+    tracing::debug!(?available, "Available Ren'Py SDKs");
+
+    let Some(version) = version else {
+        tracing::warn!("Could not detect the game's Ren'Py version, using the newest SDK");
+        return available
+            .into_iter()
+            .next()
+            .map(|(sdk, _)| sdk)
+            .ok_or_else(|| {
+                stable_eyre::eyre::eyre!("No usable Ren'Py SDK configured in renpy_sdks")
+            });
+    };
+
+    let found = available
+        .iter()
+        .find(|(_, v)| *v == version)
+        .or_else(|| {
+            available
+                .iter()
+                .find(|(_, v)| v.major == version.major && v.minor == version.minor)
+        })
+        .or_else(|| {
+            available
+                .iter()
+                .find(|(_, v)| v.major == version.major && *v >= version)
+        });
+
+    match found {
+        Some((sdk, sdk_version)) => {
+            tracing::info!(game_version = %version, %sdk_version, ?sdk, "Selected Ren'Py SDK");
+            Ok(sdk.clone())
+        }
+        None => Err(stable_eyre::eyre::eyre!(
+            "No Ren'Py SDK compatible with version {} configured, available: [{}]",
+            version,
+            available
+                .iter()
+                .map(|(sdk, v)| format!("{} ({})", sdk.display(), v))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Where Ren'Py on Linux keeps saves for this game, `~/.renpy/<save_directory>`
+///
+/// Respects `RENPY_PATH_TO_SAVES` the same way Ren'Py does.
+pub fn save_path(save_directory: &str) -> Option<PathBuf> {
+    let root = std::env::var_os("RENPY_PATH_TO_SAVES")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".renpy")))?;
+    Some(root.join(save_directory))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_renpy(base: &Path, vc_version: Option<&str>, init: &str) {
+        std::fs::create_dir_all(base.join("renpy")).unwrap();
+        std::fs::create_dir_all(base.join("game")).unwrap();
+        if let Some(vc_version) = vc_version {
+            std::fs::write(base.join("renpy/vc_version.py"), vc_version).unwrap();
+        }
+        std::fs::write(base.join("renpy/__init__.py"), init).unwrap();
+        std::fs::write(base.join("renpy.sh"), "#!/bin/sh\n").unwrap();
+    }
+
+    #[test]
+    fn test_read_version() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+
+        let renpy8 = temp_dir.path().join("renpy8");
+        fake_renpy(
+            &renpy8,
+            Some("branch = 'fix'\nnightly = False\nversion = '8.1.3.23091805'\n"),
+            "",
+        );
+        assert_eq!(
+            read_version(&renpy8),
+            Some(RenPyVersion {
+                major: 8,
+                minor: 1,
+                patch: 3
+            })
+        );
+
+        let renpy7 = temp_dir.path().join("renpy7");
+        fake_renpy(
+            &renpy7,
+            Some("vc_version = 1234\n"),
+            "version_tuple = (7, 4, 11, vc_version)\n",
+        );
+        assert_eq!(
+            read_version(&renpy7),
+            Some(RenPyVersion {
+                major: 7,
+                minor: 4,
+                patch: 11
+            })
+        );
+    }
+
+    #[test]
+    fn test_detect_and_select_sdk() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let game = temp_dir.path().join("game");
+        fake_renpy(&game, None, "version_tuple = (7, 4, 5, vc_version)\n");
+        std::fs::write(
+            game.join("game/options.rpy"),
+            "define config.name = _(\"Foo\")\ndefine config.save_directory = \"Foo-1600000000\"\n",
+        )
+        .unwrap();
+
+        let detected = detect(&game).unwrap();
+        assert_eq!(detected.save_directory.as_deref(), Some("Foo-1600000000"));
+
+        let sdk7 = temp_dir.path().join("renpy-7.4.11-sdk");
+        fake_renpy(&sdk7, Some("version = '7.4.11.2266'\n"), "");
+        let sdk8 = temp_dir.path().join("renpy-8.2.0-sdk");
+        fake_renpy(&sdk8, Some("version = '8.2.0.24012702'\n"), "");
+        let sdks = vec![sdk8.clone(), sdk7.clone()];
+
+        assert_eq!(select_sdk(detected.version, &sdks).unwrap(), sdk7);
+        assert_eq!(
+            select_sdk(
+                Some(RenPyVersion {
+                    major: 8,
+                    minor: 0,
+                    patch: 3
+                }),
+                &sdks
+            )
+            .unwrap(),
+            sdk8
+        );
+        assert!(select_sdk(
+            Some(RenPyVersion {
+                major: 6,
+                minor: 99,
+                patch: 14
+            }),
+            &sdks
+        )
+        .is_err());
+    }
+}
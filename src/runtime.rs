@@ -3,11 +3,20 @@
 //! This actually does the actual calling logic to we can actually run the games and stuff
 //!
 //! should replace the messy spaghetti in main.rs
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    config::{GameConfig, SteamCompatConfig},
-    path_search::get_asar_path,
+    config::{CompatType, GameConfig, SteamCompatConfig},
+    electron, flash, godot, hashlink, java,
+    launch_args::{
+        map_steam_args, shell_quote, shell_script, steam_env, LaunchContext, DEFAULT_ARG_TEMPLATE,
+    },
+    love, mkxp, mono, path_search,
+    path_search::{get_asar_path, get_nwjs_path, get_web_root},
+    renpy, runtimes, steam, supervise,
 };
 use stable_eyre::Result;
 #[derive(Debug)]
@@ -18,64 +27,269 @@ pub struct Runtime {
     pub compat_tool_path: std::path::PathBuf,
 }
 
+/// Game target detected for the configured compat type
+#[derive(Debug, Default)]
+struct DetectedTarget {
+    /// What gets passed to the runtime, `%exe%` in the argument template
+    target: PathBuf,
+    /// Runtime binary picked for this specific game, e.g. to match the engine version
+    ///
+    /// `wrapper_command` still takes priority over this
+    runtime: Option<String>,
+    /// Extra wrapper arguments, passed after the configured ones
+    wrapper_args: Vec<String>,
+    /// Extra environment variables, `env_vars` from the config take priority
+    env: BTreeMap<String, String>,
+    /// Extra directories appended to `LD_LIBRARY_PATH`
+    library_paths: Vec<PathBuf>,
+    /// Working directory to launch the game from, Steam's is kept if not set
+    working_dir: Option<PathBuf>,
+    /// Files to create before launching, which the paths above may point to
+    materialize: Vec<Materialize>,
+}
+
+/// A file the launch needs created before the game starts
+///
+/// Resolving the target only plans these, so a dry run leaves the compat data untouched.
+/// [`Runtime::launch_game`] carries them out, [`Runtime::dry_run`] writes them into the script.
+#[derive(Debug)]
+enum Materialize {
+    /// Write a generated config, e.g. the Mono config or `mkxp.json`
+    WriteFile { path: PathBuf, contents: String },
+    /// Link HashLink native modules into a directory, replacing what's there
+    LinkModules {
+        dir: PathBuf,
+        modules: BTreeMap<String, PathBuf>,
+    },
+    /// Extract the SWF embedded in a Flash projector
+    ExtractSwf {
+        exe: PathBuf,
+        offset: u64,
+        len: u64,
+        cache_dir: PathBuf,
+    },
+    /// Extract the archive fused into a LOVE executable
+    ExtractFused {
+        exe: PathBuf,
+        offset: u64,
+        love_path: PathBuf,
+    },
+}
+
+impl Materialize {
+    fn run(&self) -> Result<()> {
+        match self {
+            Materialize::WriteFile { path, contents } => {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(path, contents)?;
+                tracing::debug!(?path, "Wrote generated config");
+            }
+            Materialize::LinkModules { dir, modules } => hashlink::link_modules(modules, dir)?,
+            Materialize::ExtractSwf {
+                exe,
+                offset,
+                len,
+                cache_dir,
+            } => {
+                flash::extract_swf(exe, *offset, *len, cache_dir)?;
+            }
+            Materialize::ExtractFused {
+                exe,
+                offset,
+                love_path,
+            } => love::extract_fused(exe, *offset, love_path)?,
+        }
+        Ok(())
+    }
+
+    /// Shell commands doing the same, for dry runs
+    fn shell_commands(&self) -> Vec<String> {
+        let quote = |path: &Path| shell_quote(&path.to_string_lossy());
+        let mkdir =
+            |dir: Option<&Path>| format!("mkdir -p {}", quote(dir.unwrap_or(Path::new("."))));
+        match self {
+            Materialize::WriteFile { path, contents } => {
+                let mut delimiter = "BOSON_EOF".to_string();
+                while contents.lines().any(|line| line == delimiter) {
+                    delimiter.push('_');
+                }
+                let newline = if contents.ends_with('\n') { "" } else { "\n" };
+                vec![
+                    mkdir(path.parent()),
+                    format!(
+                        "cat > {} <<'{delimiter}'\n{contents}{newline}{delimiter}",
+                        quote(path)
+                    ),
+                ]
+            }
+            Materialize::LinkModules { dir, modules } => {
+                let mut commands = vec![format!("rm -rf {}", quote(dir)), mkdir(Some(dir))];
+                commands.extend(modules.iter().map(|(module, path)| {
+                    format!(
+                        "ln -s {} {}",
+                        quote(path),
+                        quote(&dir.join(format!("{module}.hdll")))
+                    )
+                }));
+                commands
+            }
+            Materialize::ExtractSwf {
+                exe,
+                offset,
+                len,
+                cache_dir,
+            } => vec![
+                mkdir(Some(cache_dir)),
+                format!(
+                    "tail -c +{} {} | head -c {} > {}",
+                    offset + 1,
+                    quote(exe),
+                    len,
+                    quote(&flash::swf_path(exe, cache_dir))
+                ),
+            ],
+            Materialize::ExtractFused {
+                exe,
+                offset,
+                love_path,
+            } => {
+                let dir = love_path.parent();
+                let mut commands = vec![
+                    mkdir(dir),
+                    format!(
+                        "tail -c +{} {} > {}",
+                        offset + 1,
+                        quote(exe),
+                        quote(love_path)
+                    ),
+                ];
+                // Files next to the executable, as the game may mount them relative to itself
+                let siblings = exe
+                    .parent()
+                    .and_then(|game_dir| std::fs::read_dir(game_dir).ok())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p != exe)
+                    .collect::<Vec<_>>();
+                if let Some(dir) = dir {
+                    commands.extend(siblings.iter().filter_map(|sibling| {
+                        let link = dir.join(sibling.file_name()?);
+                        (link != *love_path)
+                            .then(|| format!("ln -sfn {} {}", quote(sibling), quote(&link)))
+                    }));
+                }
+                commands
+            }
+        }
+    }
+}
+
+/// The verb Steam invoked the compatibility tool with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verb {
+    Run,
+    /// Used for the game's first process, Proton waits for the prefix to be ready
+    /// and for wineserver to exit
+    WaitForExitAndRun,
+    /// Translate a host path into a path the game understands
+    GetCompatPath,
+    /// Translate a path from the game into a host path
+    GetNativePath,
+}
+
+impl Verb {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verb::Run => "run",
+            Verb::WaitForExitAndRun => "waitforexitandrun",
+            Verb::GetCompatPath => "getcompatpath",
+            Verb::GetNativePath => "getnativepath",
+        }
+    }
+}
+
+impl std::fmt::Display for Verb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct ToolManifest {
     pub commandline: String,
     pub commandline_waitforexitandrun: Option<String>,
+    pub commandline_getcompatpath: Option<String>,
+    pub commandline_getnativepath: Option<String>,
 }
 
-fn lookup_compat_tool(
-    path: &str,
-    steam_compat_config: &SteamCompatConfig,
-) -> Result<Option<PathBuf>> {
-    // Build the list of library paths to search. The STEAM_LIBRARY_PATHS env/config is required.
-    let mut lib_paths = std::env::split_paths(
-        steam_compat_config
-            .library_paths
-            .as_ref()
-            .ok_or_else(|| stable_eyre::eyre::eyre!("STEAM_LIBRARY_PATHS is not set"))?,
-    )
-    .map(|p| p.join("common"))
-    .collect::<Vec<_>>();
-
-    // If a client install path is configured, add its compatibilitytools.d folder to the search list.
-    if let Some(client_install) = &steam_compat_config.client_install_path {
-        lib_paths.push(client_install.join("compatibilitytools.d"));
+impl ToolManifest {
+    /// The command line Steam would use for this verb, with `%verb%` still in place
+    pub fn commandline_for(&self, verb: Verb) -> &str {
+        let specific = match verb {
+            Verb::Run => None,
+            Verb::WaitForExitAndRun => self.commandline_waitforexitandrun.as_ref(),
+            Verb::GetCompatPath => self.commandline_getcompatpath.as_ref(),
+            Verb::GetNativePath => self.commandline_getnativepath.as_ref(),
+        };
+        specific.unwrap_or(&self.commandline)
     }
+}
+
+/// Find a compatibility tool by directory name, display name, internal name or Proton app ID
+fn lookup_compat_tool(path: &str, steam_compat_config: &SteamCompatConfig) -> Result<PathBuf> {
+    let tool_dirs = steam::compat_tool_dirs(steam_compat_config);
 
     tracing::trace!(
-        ?lib_paths,
+        ?tool_dirs,
         "Library paths to search for compatibility tools"
     );
 
-    // Search each library path for a directory matching `path`.
-    for base in lib_paths {
+    // Search each library path for a directory matching `path` first, it's the cheapest
+    for base in tool_dirs {
         let candidate = base.join(path);
         tracing::trace!(?candidate, "Checking for compatibility tool candidate");
         if candidate.exists() && candidate.is_dir() {
             tracing::debug!(?candidate, "Found compatibility tool");
-            return Ok(Some(candidate));
+            return Ok(candidate);
         }
     }
 
-    tracing::debug!(
-        path = path,
-        "Compatibility tool not found in configured library paths"
-    );
-    Ok(None)
+    let tools = steam::index_compat_tools(steam_compat_config);
+    tracing::trace!(?tools, "Indexed compatibility tools");
+    if let Some(tool) = steam::find_compat_tool(&tools, path) {
+        tracing::debug!(%tool, path = ?tool.path, "Found compatibility tool");
+        return Ok(tool.path.clone());
+    }
+
+    Err(stable_eyre::eyre::eyre!(
+        "Compatibility tool '{}' not found. Available tools: [{}]",
+        path,
+        tools
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
 }
 
 /// Takes in a string path and returns the path to the compatibility tool wrapper if it exists
 /// i.e defer to Proton
-fn get_compat_tool_wrapper(path: &Path) -> Result<Option<(std::path::PathBuf, Vec<String>)>> {
+///
+/// The command line is picked for the verb like Steam would, `%verb%` is left for the caller
+fn get_compat_tool_wrapper(
+    path: &Path,
+    verb: Verb,
+) -> Result<Option<(std::path::PathBuf, Vec<String>)>> {
     let toolmanifest_file = path.join("toolmanifest.vdf");
     // manifest.
     let toolmanifest_file = std::fs::File::open(toolmanifest_file)?;
     let toolmanifest: ToolManifest = keyvalues_serde::from_reader(toolmanifest_file)?;
-    let cmdline = toolmanifest
-        .commandline
-        .strip_prefix('/')
-        .unwrap_or(&toolmanifest.commandline);
+    let commandline = toolmanifest.commandline_for(verb);
+    let cmdline = commandline.strip_prefix('/').unwrap_or(commandline);
 
     let mut iter = cmdline.split_whitespace();
     if let Some(first) = iter.next() {
@@ -95,6 +309,66 @@ fn get_compat_tool_wrapper(path: &Path) -> Result<Option<(std::path::PathBuf, Ve
     // todo!()
 }
 
+/// Handle the `getcompatpath` and `getnativepath` verbs, printing one translated path per line
+///
+/// DeferProton games hand the verb to the underlying tool, as it's the one running the game.
+/// Otherwise Windows paths are translated relative to the game's prefix in the compat data.
+pub fn translate_paths(
+    steam_opts: &SteamCompatConfig,
+    game_config: &GameConfig,
+    verb: Verb,
+    paths: &[String],
+) -> Result<()> {
+    if game_config.compat_type == CompatType::DeferProton {
+        if let Some(tool_dir) = game_config.compat_tool_dir.as_deref() {
+            let found = lookup_compat_tool(tool_dir, steam_opts)?;
+            let (wrapper, mut wrapper_args) =
+                get_compat_tool_wrapper(&found, verb)?.ok_or_else(|| {
+                    stable_eyre::eyre::eyre!("Could not parse compatibility tool command line")
+                })?;
+            wrapper_args
+                .iter_mut()
+                .for_each(|arg| *arg = arg.replace("%verb%", verb.as_str()));
+            tracing::debug!(?wrapper, ?wrapper_args, %verb, "Delegating verb to compatibility tool");
+
+            let status = std::process::Command::new(&wrapper)
+                .args(wrapper_args)
+                .args(paths)
+                .status()?;
+            if !status.success() {
+                return Err(stable_eyre::eyre::eyre!(
+                    "{:?} {} failed: {}",
+                    wrapper,
+                    verb,
+                    status
+                ));
+            }
+            return Ok(());
+        }
+        tracing::warn!(
+            "DeferProton set but no compat_tool_dir configured, translating paths ourselves"
+        );
+    }
+
+    let prefix = steam_opts.data_path.as_ref().map(|p| p.join("pfx"));
+    for path in paths {
+        let translated = match verb {
+            Verb::GetNativePath => path_search::windows_to_native(path, prefix.as_deref())?
+                .display()
+                .to_string(),
+            Verb::GetCompatPath => {
+                path_search::native_to_windows(Path::new(path), prefix.as_deref())?
+            }
+            Verb::Run | Verb::WaitForExitAndRun => {
+                return Err(stable_eyre::eyre::eyre!("{} is not a path verb", verb))
+            }
+        };
+        tracing::debug!(%verb, path, translated, "Translated path");
+        println!("{translated}");
+    }
+    Ok(())
+}
+
 fn shellexpand_full_no_errors(s: &str) -> std::borrow::Cow<'_, str> {
     let home: String = dirs::home_dir()
         .and_then(|p| p.to_str().map(|s| s.to_owned()))
@@ -128,23 +402,447 @@ impl Runtime {
         }
     }
 
-    pub fn launch_game(&self, additional_args: Vec<String>) -> Result<()> {
-        tracing::trace!(?self, ?additional_args, "Launching game");
-
-        let executable_path: std::path::PathBuf =
-            if let Some(cmd) = &self.game_config.command_override {
-                PathBuf::from(shellexpand_full_no_errors(cmd).to_string())
-            } else {
-                match &self.game_config.compat_type {
-                    &crate::config::CompatType::Electron => {
-                        // find the ASAR path
-                        get_asar_path(&self.exec_path).ok_or_else(|| {
-                            stable_eyre::eyre::eyre!("Could not find ASAR path for Electron game")
+    /// The game's install directory, from Steam if available
+    /// or the directory containing the game executable otherwise
+    fn game_dir(&self) -> Option<PathBuf> {
+        self.steam_opts
+            .install_path
+            .clone()
+            .or_else(|| self.exec_path.parent().map(Path::to_path_buf))
+    }
+
+    /// Find the game target for the configured compat type, and the runtime to run it with
+    /// if the compat type picks one per game
+    fn resolve_target(&self) -> Result<DetectedTarget> {
+        if let Some(cmd) = &self.game_config.command_override {
+            return Ok(DetectedTarget {
+                target: PathBuf::from(shellexpand_full_no_errors(cmd).to_string()),
+                ..Default::default()
+            });
+        }
+
+        match &self.game_config.compat_type {
+            CompatType::Electron => {
+                // find the ASAR path
+                let target = get_asar_path(&self.exec_path).ok_or_else(|| {
+                    stable_eyre::eyre::eyre!("Could not find ASAR path for Electron game")
+                })?;
+
+                let version = electron::detect_version(&self.exec_path, &target);
+                match version {
+                    Some((version, source)) => {
+                        tracing::info!("Electron version {} (from {})", version, source)
+                    }
+                    None => tracing::warn!(
+                        "Could not find a version file, executable version or package.json electron dependency"
+                    ),
+                }
+                let electron_paths: Vec<PathBuf> = self
+                    .game_config
+                    .electron_paths
+                    .iter()
+                    .map(|p| PathBuf::from(shellexpand_full_no_errors(p).to_string()))
+                    .chain(runtimes::installed_electrons(&runtimes::runtimes_dir()))
+                    .chain(electron::host_electron_candidates())
+                    .collect();
+                let binary = electron::select_electron(version.map(|(v, _)| v), &electron_paths);
+                Ok(DetectedTarget {
+                    target,
+                    runtime: Some(binary),
+                    ..Default::default()
+                })
+            }
+            CompatType::NwJs => {
+                let target = get_nwjs_path(&self.exec_path).ok_or_else(|| {
+                    stable_eyre::eyre::eyre!("Could not find app path for NW.js game")
+                })?;
+                Ok(DetectedTarget {
+                    target,
+                    ..Default::default()
+                })
+            }
+            CompatType::Godot => {
+                let (target, header) = godot::find_pack(&self.exec_path).ok_or_else(|| {
+                    stable_eyre::eyre::eyre!("Could not find a PCK data pack for Godot game")
+                })?;
+                tracing::info!(
+                    "Godot engine version {}, pack format {}",
+                    header,
+                    header.format_version
+                );
+                let binary = godot::select_binary(&header, &self.game_config.godot_binaries);
+                Ok(DetectedTarget {
+                    target,
+                    runtime: Some(shellexpand_full_no_errors(&binary).to_string()),
+                    ..Default::default()
+                })
+            }
+            CompatType::RenPy => {
+                let base_dir = self.exec_path.parent().unwrap_or(Path::new("."));
+                let game = renpy::detect(base_dir).ok_or_else(|| {
+                    stable_eyre::eyre::eyre!(
+                        "Could not find renpy/ and game/ directories next to {:?}",
+                        self.exec_path
+                    )
+                })?;
+                tracing::info!(
+                    "Ren'Py version {}",
+                    game.version
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| "unknown".to_string())
+                );
+
+                if let Some(save_directory) = &game.save_directory {
+                    tracing::info!(
+                        "Ren'Py saves will be stored in {:?}",
+                        renpy::save_path(save_directory)
+                    );
+                }
+
+                let sdks = self
+                    .game_config
+                    .renpy_sdks
+                    .iter()
+                    .map(|sdk| PathBuf::from(shellexpand_full_no_errors(sdk).to_string()))
+                    .collect::<Vec<_>>();
+                let sdk = renpy::select_sdk(game.version, &sdks)?;
+                Ok(DetectedTarget {
+                    target: game.base_dir,
+                    runtime: Some(sdk.join("renpy.sh").display().to_string()),
+                    ..Default::default()
+                })
+            }
+            CompatType::Mono => {
+                let game = mono::detect(&self.exec_path)?;
+                tracing::info!(
+                    "Running {:?} with Mono, framework: {:?}",
+                    game.assembly,
+                    game.framework
+                );
+                if game.framework == mono::Framework::Unknown {
+                    tracing::warn!(
+                        "No XNA, FNA or MonoGame assemblies found, running the assembly as-is"
+                    );
+                }
+
+                let fna_dir = self.compat_tool_path.join("lib").join("fna");
+                mono::check_fna_dir(&fna_dir, game.framework);
+
+                let config = mono::config_path(&self.compat_data_dir());
+
+                // Boson's FNA goes last, so games shipping their own FNA.dll keep using it
+                let mut mono_path = std::env::var_os("MONO_PATH")
+                    .map(|p| std::env::split_paths(&p).collect::<Vec<_>>())
+                    .unwrap_or_default();
+                mono_path.push(fna_dir.clone());
+
+                Ok(DetectedTarget {
+                    target: game.assembly,
+                    wrapper_args: vec!["--config".to_string(), config.display().to_string()],
+                    env: [(
+                        "MONO_PATH".to_string(),
+                        std::env::join_paths(mono_path)?
+                            .to_string_lossy()
+                            .to_string(),
+                    )]
+                    .into(),
+                    library_paths: vec![fna_dir],
+                    materialize: vec![Materialize::WriteFile {
+                        path: config,
+                        contents: mono::host_config(),
+                    }],
+                    ..Default::default()
+                })
+            }
+            CompatType::Java => {
+                let game = java::detect(&self.exec_path)?;
+                tracing::debug!(?game, "Detected Java game");
+
+                let java_homes = self
+                    .game_config
+                    .java_homes
+                    .iter()
+                    .map(|home| PathBuf::from(shellexpand_full_no_errors(home).to_string()))
+                    .chain(std::env::var_os("JAVA_HOME").map(PathBuf::from))
+                    .collect::<Vec<_>>();
+                let java = java::select_java(game.java_version, &java_homes)?;
+
+                let mut wrapper_args = game.vm_args.clone();
+                let game_dir = self.exec_path.parent().unwrap_or(Path::new("."));
+                if java::needs_lwjgl_natives(&game, game_dir) {
+                    let lwjgl_dir = self.compat_tool_path.join("lib").join("lwjgl");
+                    tracing::info!(
+                        ?lwjgl_dir,
+                        "Game only bundles Windows LWJGL natives, using Boson's"
+                    );
+                    wrapper_args.push(format!("-Djava.library.path={}", lwjgl_dir.display()));
+                    wrapper_args.push(format!("-Dorg.lwjgl.librarypath={}", lwjgl_dir.display()));
+                }
+
+                let target = match game.main_class {
+                    Some(main_class) => {
+                        let class_path = std::env::join_paths(&game.class_path)?;
+                        wrapper_args.push("-cp".to_string());
+                        wrapper_args.push(class_path.to_string_lossy().to_string());
+                        PathBuf::from(main_class)
+                    }
+                    None => {
+                        wrapper_args.push("-jar".to_string());
+                        game.class_path[0].clone()
+                    }
+                };
+
+                Ok(DetectedTarget {
+                    target,
+                    runtime: Some(java),
+                    wrapper_args,
+                    ..Default::default()
+                })
+            }
+            CompatType::HashLink => {
+                let game_dir = self.exec_path.parent().unwrap_or(Path::new("."));
+                let target = hashlink::find_bytecode(game_dir).ok_or_else(|| {
+                    stable_eyre::eyre::eyre!(
+                        "Could not find hlboot.dat or a .hl file in {:?}",
+                        game_dir
+                    )
+                })?;
+                tracing::info!(?target, "Found HashLink bytecode");
+
+                let module_map = self
+                    .game_config
+                    .hashlink_modules
+                    .iter()
+                    .map(|(module, path)| {
+                        (module.clone(), shellexpand_full_no_errors(path).to_string())
+                    })
+                    .collect();
+                let modules = hashlink::resolve_modules(
+                    &hashlink::game_modules(game_dir),
+                    &module_map,
+                    &self.compat_tool_path.join("lib").join("hashlink"),
+                );
+                let modules_dir = self.compat_data_dir().join("hashlink");
+
+                Ok(DetectedTarget {
+                    target,
+                    library_paths: vec![modules_dir.clone()],
+                    materialize: vec![Materialize::LinkModules {
+                        dir: modules_dir,
+                        modules,
+                    }],
+                    ..Default::default()
+                })
+            }
+            CompatType::Mkxp => {
+                let game = mkxp::detect(&self.exec_path)?;
+                tracing::info!(
+                    "RGSS{} game, archive: {:?}",
+                    game.rgss_version,
+                    game.archive
+                );
+
+                let rtp_paths = self
+                    .game_config
+                    .mkxp_rtps
+                    .iter()
+                    .map(|(rtp, path)| (rtp.clone(), shellexpand_full_no_errors(path).to_string()))
+                    .collect();
+                let soundfont = self
+                    .game_config
+                    .mkxp_soundfont
+                    .as_deref()
+                    .map(|sf| shellexpand_full_no_errors(sf).to_string());
+                let soundfont = mkxp::find_soundfont(soundfont.as_deref());
+                let config = mkxp::generate_config(&game, &rtp_paths, soundfont.as_deref());
+
+                let config_dir = mkxp::config_dir(&self.compat_data_dir());
+
+                Ok(DetectedTarget {
+                    target: game.game_dir,
+                    working_dir: Some(config_dir.clone()),
+                    materialize: vec![Materialize::WriteFile {
+                        path: config_dir.join("mkxp.json"),
+                        contents: serde_json::to_string_pretty(&config)?,
+                    }],
+                    ..Default::default()
+                })
+            }
+            CompatType::Flash => {
+                let mut materialize = vec![];
+                let target = match flash::find_embedded_swf(&self.exec_path)? {
+                    Some((offset, len)) => {
+                        let cache_dir = self.compat_data_dir().join("flash");
+                        let swf_path = flash::swf_path(&self.exec_path, &cache_dir);
+                        materialize.push(Materialize::ExtractSwf {
+                            exe: self.exec_path.clone(),
+                            offset,
+                            len,
+                            cache_dir,
+                        });
+                        swf_path
+                    }
+                    None => {
+                        let game_dir = self.exec_path.parent().unwrap_or(Path::new("."));
+                        flash::find_loose_swf(game_dir).ok_or_else(|| {
+                            stable_eyre::eyre::eyre!(
+                                "{:?} is not a Flash projector, and no SWF was found next to it",
+                                self.exec_path
+                            )
                         })?
                     }
-                    _ => self.exec_path.clone(),
+                };
+                tracing::info!(?target, "Running SWF with Ruffle");
+
+                Ok(DetectedTarget {
+                    target,
+                    wrapper_args: flash::ruffle_args(
+                        self.game_config.ruffle_scale.as_deref(),
+                        self.game_config.ruffle_quality.as_deref(),
+                        self.game_config.ruffle_letterbox.as_deref(),
+                    ),
+                    materialize,
+                    ..Default::default()
+                })
+            }
+            CompatType::Love => {
+                let version = love::detect_version(&self.exec_path);
+                match version {
+                    Some((version, source)) => {
+                        tracing::info!("LOVE version {} (from {})", version, source)
+                    }
+                    None => tracing::warn!(
+                        "Could not find t.version in conf.lua or a love.dll version resource"
+                    ),
+                }
+                let binary =
+                    love::select_binary(version.map(|(v, _)| v), &self.game_config.love_binaries);
+
+                let exe_dir = self.exec_path.parent().map(Path::to_path_buf);
+                let mut materialize = vec![];
+                let target = match love::find_fused_archive(&self.exec_path)? {
+                    Some(offset) if offset > 0 => {
+                        let cache_dir = self.compat_data_dir().join("love");
+                        let love_path = love::fused_path(&self.exec_path, &cache_dir)?;
+                        materialize.push(Materialize::ExtractFused {
+                            exe: self.exec_path.clone(),
+                            offset,
+                            love_path: love_path.clone(),
+                        });
+                        love_path
+                    }
+                    // Already a plain .love archive, or a fused one LOVE can read as is
+                    Some(_) => self.exec_path.clone(),
+                    None => {
+                        tracing::warn!(
+                            "No fused .love archive found in the executable, running it as is"
+                        );
+                        self.exec_path.clone()
+                    }
+                };
+                Ok(DetectedTarget {
+                    target,
+                    runtime: Some(shellexpand_full_no_errors(&binary).to_string()),
+                    working_dir: exe_dir,
+                    materialize,
+                    ..Default::default()
+                })
+            }
+            CompatType::Web => {
+                let target = get_web_root(&self.exec_path).ok_or_else(|| {
+                    stable_eyre::eyre::eyre!("Could not find an index.html web root for web game")
+                })?;
+
+                let mut env =
+                    BTreeMap::from([("BOSON_WEB_ROOT".to_string(), target.display().to_string())]);
+                if let Some(width) = self.game_config.web_width {
+                    env.insert("BOSON_WEB_WIDTH".to_string(), width.to_string());
+                }
+                if let Some(height) = self.game_config.web_height {
+                    env.insert("BOSON_WEB_HEIGHT".to_string(), height.to_string());
                 }
-            };
+                if let Some(fullscreen) = self.game_config.web_fullscreen {
+                    env.insert(
+                        "BOSON_WEB_FULLSCREEN".to_string(),
+                        (fullscreen as u8).to_string(),
+                    );
+                }
+                if let Some(zoom) = self.game_config.web_zoom {
+                    env.insert("BOSON_WEB_ZOOM".to_string(), zoom.to_string());
+                }
+
+                Ok(DetectedTarget {
+                    target,
+                    // Boson's generic main script, which opens the web root in a window
+                    wrapper_args: vec![self
+                        .compat_tool_path
+                        .join("web-shell")
+                        .display()
+                        .to_string()],
+                    env,
+                    ..Default::default()
+                })
+            }
+            _ => Ok(DetectedTarget {
+                target: self.exec_path.clone(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Launch the game and wait for it to exit, returning the game's exit status
+    ///
+    /// A game exiting with an error is not an error here, only failing to launch it is.
+    pub fn launch_game(
+        &self,
+        verb: Verb,
+        additional_args: Vec<String>,
+    ) -> Result<std::process::ExitStatus> {
+        let (mut cmd, materialize) = self.build_command(verb, additional_args)?;
+        for step in &materialize {
+            step.run()?;
+        }
+        tracing::info!("Launching game with command: {:?}", cmd);
+
+        let grace_period = std::time::Duration::from_secs(
+            self.game_config
+                .kill_grace_period
+                .unwrap_or(supervise::DEFAULT_KILL_GRACE_PERIOD),
+        );
+        supervise::run(&mut cmd, grace_period)
+    }
+
+    /// Resolve the launch like [`Runtime::launch_game`] would, as a shell script instead
+    /// of running it, so it can be rerun outside of Steam
+    ///
+    /// Nothing is written, the files the launch needs are created by the script instead.
+    pub fn dry_run(&self, verb: Verb, additional_args: Vec<String>) -> Result<String> {
+        let (cmd, materialize) = self.build_command(verb, additional_args)?;
+        let setup: Vec<String> = materialize
+            .iter()
+            .flat_map(Materialize::shell_commands)
+            .collect();
+        Ok(shell_script(&cmd, &steam_env(), &setup))
+    }
+
+    /// Boson's directory in the game's compat data, for generated configs and extracted files
+    fn compat_data_dir(&self) -> PathBuf {
+        match &self.steam_opts.data_path {
+            Some(data_path) => data_path.join("boson"),
+            None => path_search::compat_data_path(self.steam_opts.get_app_id().unwrap_or_default()),
+        }
+    }
+
+    /// Build the command that launches the game, along with the files it needs created first
+    fn build_command(
+        &self,
+        verb: Verb,
+        additional_args: Vec<String>,
+    ) -> Result<(std::process::Command, Vec<Materialize>)> {
+        tracing::trace!(?self, %verb, ?additional_args, "Launching game");
+
+        let detected = self.resolve_target()?;
+        let executable_path = detected.target.clone();
 
         let boson_lib_dir = self.compat_tool_path.join("lib");
 
@@ -152,26 +850,22 @@ impl Runtime {
         let (wrapper, wrapper_args) = match &self.game_config.compat_type {
             crate::config::CompatType::DeferProton => {
                 if let Some(tool_dir) = self.game_config.compat_tool_dir.as_deref() {
-                    let found =
-                        lookup_compat_tool(tool_dir, &self.steam_opts)?.ok_or_else(|| {
-                            stable_eyre::eyre::eyre!(
-                                "Compatibility tool '{}' not found in configured library paths",
-                                tool_dir
-                            )
-                        })?;
+                    let found = lookup_compat_tool(tool_dir, &self.steam_opts)?;
 
                     tracing::debug!(?found, "Using compatibility tool from config");
 
                     // Get wrapper command and args from the compat tool
                     if let Some((wrapper_path, mut tool_wrapper_args)) =
-                        get_compat_tool_wrapper(&found)?
+                        get_compat_tool_wrapper(&found, verb)?
                     {
                         // Combine tool wrapper args with user config wrapper args
                         tool_wrapper_args.extend(self.game_config.wrapper_args.clone());
 
+                        // Forward the verb Steam gave us, Proton relies on it to know
+                        // whether to wait for the prefix and wineserver
                         tool_wrapper_args
                             .iter_mut()
-                            .for_each(|arg| *arg = arg.replace("%verb%", "run"));
+                            .for_each(|arg| *arg = arg.replace("%verb%", verb.as_str()));
 
                         let wrapper_cmd = wrapper_path.to_string_lossy().to_string();
                         tracing::debug!(
@@ -203,12 +897,14 @@ impl Runtime {
                     .game_config
                     .wrapper_command
                     .as_ref()
+                    .or(detected.runtime.as_ref())
                     .or(wrapper_default.as_ref())
                     .cloned();
 
                 let wrapper_args = {
                     let mut args = self.game_config.wrapper_args.clone();
                     args.extend(wrapper_extras_default);
+                    args.extend(detected.wrapper_args.iter().cloned());
                     args
                 };
 
@@ -270,25 +966,45 @@ impl Runtime {
                 paths.push(boson_lib_dir.display().to_string());
             }
 
+            paths.extend(
+                detected
+                    .library_paths
+                    .iter()
+                    .map(|p| p.display().to_string()),
+            );
+
             if self.game_config.disable_steam_overlay {
                 // filter out gameoverlayrenderer
-                paths = paths
-                    .into_iter()
-                    .filter(|s| !s.contains("gameoverlayrenderer"))
-                    .collect();
+                paths.retain(|s| !s.contains("gameoverlayrenderer"));
             }
             paths.join(":")
         };
 
+        let launch_context = LaunchContext {
+            exe: executable_path.clone(),
+            game_dir: self.game_dir(),
+            app_id: self.steam_opts.get_app_id().unwrap_or_default(),
+            compat_data: self.steam_opts.data_path.clone(),
+            wrapper_args,
+            steam_args: map_steam_args(&additional_args, &self.game_config.arg_map),
+            append_args: self.game_config.append_args.clone(),
+        };
+
+        let arg_template = self
+            .game_config
+            .arg_template
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ARG_TEMPLATE.iter().map(|s| s.to_string()).collect());
+        tracing::debug!(?arg_template, "Argument template");
+
         let mut cmd = if let Some(wrapper) = wrapper {
             let mut cmd = std::process::Command::new(wrapper);
-            cmd.args(&wrapper_args);
-            cmd.args(&additional_args);
-            cmd.arg(&executable_path);
+            cmd.args(launch_context.expand(&arg_template, true));
             cmd
         } else {
+            // No wrapper, the game target is the program itself
             let mut cmd = std::process::Command::new(&executable_path);
-            cmd.args(&additional_args);
+            cmd.args(launch_context.expand(&arg_template, false));
             cmd
         };
 
@@ -305,21 +1021,95 @@ impl Runtime {
             cmd.env("LUA_PATH", format!("{libdir_str}/love/?.lua;;"));
         }
 
+        for (key, value) in &detected.env {
+            cmd.env(key, value);
+        }
+
+        if let Some(working_dir) = &detected.working_dir {
+            tracing::debug!(?working_dir, "Working directory");
+            cmd.current_dir(working_dir);
+        }
+
         // Add extra envars
         for (key, value) in &self.game_config.env_vars {
             cmd.env(key, shellexpand_full_no_errors(value).to_string());
         }
 
-        tracing::info!("Launching game with command: {:?}", cmd);
+        Ok((cmd, detected.materialize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compat_tool_wrapper_for_verb() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("toolmanifest.vdf"),
+            r#""manifest"
+{
+  "commandline" "/proton %verb%"
+  "commandline_waitforexitandrun" "/proton waitforexitandrun --wait"
+}
+"#,
+        )
+        .unwrap();
+
+        let (wrapper, args) = get_compat_tool_wrapper(temp_dir.path(), Verb::Run)
+            .unwrap()
+            .unwrap();
+        assert_eq!(wrapper, temp_dir.path().join("proton"));
+        assert_eq!(args, vec!["%verb%"]);
 
-        let mut child = cmd.spawn()?;
-        let status = child.wait()?;
-        if !status.success() {
-            return Err(stable_eyre::eyre::eyre!(
-                "Game exited with non-zero status: {}",
-                status
-            ));
+        let (_, args) = get_compat_tool_wrapper(temp_dir.path(), Verb::WaitForExitAndRun)
+            .unwrap()
+            .unwrap();
+        assert_eq!(args, vec!["waitforexitandrun", "--wait"]);
+    }
+
+    #[test]
+    fn test_dry_run_leaves_compat_data_untouched() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let game_dir = temp_dir.path().join("game");
+        std::fs::create_dir(&game_dir).unwrap();
+        for file in ["hlboot.dat", "fmt.hdll", "hl.exe"] {
+            std::fs::write(game_dir.join(file), b"").unwrap();
         }
-        Ok(())
+        let compat_tool_path = temp_dir.path().join("boson");
+        let boson_modules = compat_tool_path.join("lib").join("hashlink");
+        std::fs::create_dir_all(&boson_modules).unwrap();
+        std::fs::write(boson_modules.join("fmt.hdll"), b"").unwrap();
+        let compat_data = temp_dir.path().join("compatdata");
+
+        let runtime = Runtime {
+            steam_opts: SteamCompatConfig {
+                data_path: Some(compat_data.clone()),
+                ..Default::default()
+            },
+            game_config: GameConfig {
+                compat_type: CompatType::HashLink,
+                ..Default::default()
+            },
+            exec_path: game_dir.join("hl.exe"),
+            compat_tool_path,
+        };
+
+        let script = runtime.dry_run(Verb::Run, vec![]).unwrap();
+        assert!(!compat_data.exists());
+        let modules_dir = compat_data.join("boson").join("hashlink");
+        assert!(script.contains(&format!(
+            "ln -s {} {}\n",
+            boson_modules.join("fmt.hdll").display(),
+            modules_dir.join("fmt.hdll").display()
+        )));
+
+        // A real launch creates them before starting the game
+        let (_, materialize) = runtime.build_command(Verb::Run, vec![]).unwrap();
+        for step in &materialize {
+            step.run().unwrap();
+        }
+        assert!(modules_dir.join("fmt.hdll").is_symlink());
     }
 }
@@ -0,0 +1,214 @@
+//! Runtimes managed by Boson
+//!
+//! Instead of bringing their own, users can have Boson download runtime builds into
+//! `~/.local/share/boson/runtimes/<runtime>/<version>`, which are then picked up automatically.
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+use stable_eyre::Result;
+
+/// Official Electron release downloads, same default as `ELECTRON_MIRROR` in electron-download
+pub const DEFAULT_ELECTRON_MIRROR: &str = "https://github.com/electron/electron/releases/download/";
+
+/// Root directory for managed runtimes
+pub fn runtimes_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("boson")
+        .join("runtimes")
+}
+
+/// Managed Electron installs, one directory per version
+pub fn installed_electrons(runtimes_dir: &Path) -> Vec<PathBuf> {
+    let mut installs: Vec<PathBuf> = std::fs::read_dir(runtimes_dir.join("electron"))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.join("electron").is_file())
+        .collect();
+    installs.sort();
+    installs
+}
+
+/// Fetch a file from a `file://` or `http(s)://` URL
+fn fetch(url: &str) -> Result<Vec<u8>> {
+    if let Some(path) = url.strip_prefix("file://") {
+        tracing::debug!(path, "Reading from local mirror");
+        return std::fs::read(path)
+            .map_err(|e| stable_eyre::eyre::eyre!("Could not read {}: {}", path, e));
+    }
+
+    tracing::debug!(url, "Downloading");
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| stable_eyre::eyre::eyre!("Could not download {}: {}", url, e))?;
+    let mut bytes = Vec::new();
+    response.into_reader().read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Check a file against its entry in a `SHASUMS256.txt`
+///
+/// Entries are `<sha256> *<filename>` (binary mode) or `<sha256>  <filename>`.
+pub fn verify_checksum(shasums: &str, filename: &str, data: &[u8]) -> Result<()> {
+    let expected = shasums
+        .lines()
+        .find_map(|line| {
+            let (hash, name) = line.split_once(' ')?;
+            (name.trim_start().trim_start_matches('*') == filename).then_some(hash)
+        })
+        .ok_or_else(|| stable_eyre::eyre::eyre!("{} is not listed in SHASUMS256.txt", filename))?;
+
+    let actual = format!("{:x}", Sha256::digest(data));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(stable_eyre::eyre::eyre!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            filename,
+            expected,
+            actual
+        ));
+    }
+    Ok(())
+}
+
+/// Check a release version is `MAJOR.MINOR.PATCH[-prerelease]`, e.g. `30.0.1` or `31.0.0-beta.2`
+///
+/// The version ends up in paths and URLs, so anything else (like `../../x`) is rejected.
+pub fn is_valid_release_version(version: &str) -> bool {
+    let (release, prerelease) = match version.split_once('-') {
+        Some((release, prerelease)) => (release, Some(prerelease)),
+        None => (version, None),
+    };
+    let parts: Vec<&str> = release.split('.').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+        && prerelease.is_none_or(|prerelease| {
+            prerelease
+                .split('.')
+                .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        })
+}
+
+/// Download, verify and unpack an Electron release, returning its install directory
+///
+/// `mirror` is the base URL releases are under, e.g. `https://github.com/electron/electron/releases/download/`
+/// or `file:///srv/electron/`. Like `ELECTRON_MIRROR`, files are expected at `<mirror>v<version>/<file>`.
+pub fn install_electron(version: &str, mirror: &str, runtimes_dir: &Path) -> Result<PathBuf> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    if !is_valid_release_version(version) {
+        return Err(stable_eyre::eyre::eyre!(
+            "Invalid Electron version {:?}, expected MAJOR.MINOR.PATCH, e.g. 30.0.1",
+            version
+        ));
+    }
+    let install_dir = runtimes_dir.join("electron").join(version);
+    if install_dir.join("electron").is_file() {
+        tracing::info!(?install_dir, "Electron {} is already installed", version);
+        return Ok(install_dir);
+    }
+
+    let base = format!("{}/v{}", mirror.trim_end_matches('/'), version);
+    let filename = format!("electron-v{version}-linux-x64.zip");
+
+    tracing::info!("Downloading Electron {} from {}", version, base);
+    let shasums = String::from_utf8(fetch(&format!("{base}/SHASUMS256.txt"))?)?;
+    let archive = fetch(&format!("{base}/{filename}"))?;
+    verify_checksum(&shasums, &filename, &archive)?;
+    tracing::info!("Verified {} against SHASUMS256.txt", filename);
+
+    // Unpack next to the final directory first, so a failed unpack doesn't leave a broken install
+    let partial_dir = runtimes_dir
+        .join("electron")
+        .join(format!("{version}.partial"));
+    if partial_dir.exists() {
+        std::fs::remove_dir_all(&partial_dir)?;
+    }
+    std::fs::create_dir_all(&partial_dir)?;
+    zip::ZipArchive::new(std::io::Cursor::new(archive))?.extract(&partial_dir)?;
+    if !partial_dir.join("electron").is_file() {
+        std::fs::remove_dir_all(&partial_dir)?;
+        return Err(stable_eyre::eyre::eyre!(
+            "{} does not contain an electron binary",
+            filename
+        ));
+    }
+
+    if install_dir.exists() {
+        std::fs::remove_dir_all(&install_dir)?;
+    }
+    std::fs::rename(&partial_dir, &install_dir)?;
+    tracing::info!(?install_dir, "Installed Electron {}", version);
+    Ok(install_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_install_electron_from_file_mirror() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let release_dir = temp_dir.path().join("mirror/v30.0.1");
+        std::fs::create_dir_all(&release_dir).unwrap();
+
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().unix_permissions(0o755);
+        zip.start_file("electron", options).unwrap();
+        zip.write_all(b"#!/bin/sh\n").unwrap();
+        zip.start_file("version", options).unwrap();
+        zip.write_all(b"30.0.1").unwrap();
+        let archive = zip.finish().unwrap().into_inner();
+        std::fs::write(release_dir.join("electron-v30.0.1-linux-x64.zip"), &archive).unwrap();
+
+        let mirror = format!("file://{}/", temp_dir.path().join("mirror").display());
+        let runtimes = temp_dir.path().join("runtimes");
+
+        // Bad checksum
+        std::fs::write(
+            release_dir.join("SHASUMS256.txt"),
+            format!("{} *electron-v30.0.1-linux-x64.zip\n", "0".repeat(64)),
+        )
+        .unwrap();
+        assert!(install_electron("30.0.1", &mirror, &runtimes).is_err());
+        assert!(installed_electrons(&runtimes).is_empty());
+
+        std::fs::write(
+            release_dir.join("SHASUMS256.txt"),
+            format!(
+                "{:x} *electron-v30.0.1-linux-x64.zip\n",
+                Sha256::digest(&archive)
+            ),
+        )
+        .unwrap();
+        let install_dir = install_electron("v30.0.1", &mirror, &runtimes).unwrap();
+        assert_eq!(install_dir, runtimes.join("electron/30.0.1"));
+        assert_eq!(installed_electrons(&runtimes), vec![install_dir.clone()]);
+        assert_eq!(
+            crate::electron::read_version_file(&install_dir).map(|v| v.major),
+            Some(30)
+        );
+    }
+
+    #[test]
+    fn test_release_versions() {
+        assert!(is_valid_release_version("30.0.1"));
+        assert!(is_valid_release_version("31.0.0-beta.2"));
+        assert!(!is_valid_release_version("30"));
+        assert!(!is_valid_release_version("30.0.1."));
+        assert!(!is_valid_release_version("30.0.1-"));
+        assert!(!is_valid_release_version("30.0.1-beta/../x"));
+        assert!(!is_valid_release_version("../../x"));
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let runtimes = temp_dir.path().join("runtimes");
+        assert!(install_electron("../../x", "file:///nonexistent/", &runtimes).is_err());
+        assert!(!runtimes.exists());
+    }
+}
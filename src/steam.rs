@@ -0,0 +1,407 @@
+//! Steam installation discovery
+//!
+//! Steam tells compatibility tools where its libraries are through `STEAM_COMPAT_LIBRARY_PATHS`,
+//! but older clients don't set it, and it's missing entirely when Boson is run outside Steam.
+//! In that case we find the Steam install ourselves and read its `libraryfolders.vdf`.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::config::SteamCompatConfig;
+
+/// System-wide compatibility tools, usually installed by distribution packages
+const SYSTEM_COMPAT_TOOLS_DIR: &str = "/usr/share/steam/compatibilitytools.d";
+
+/// Official Proton releases, by their internal tool name (as used in Steam's
+/// `CompatToolMapping`) and app ID
+const OFFICIAL_TOOLS: [(&str, u32); 8] = [
+    ("proton_experimental", 1493710),
+    ("proton_hotfix", 2180100),
+    ("proton_10", 3658110),
+    ("proton_9", 2805730),
+    ("proton_8", 2348590),
+    ("proton_7", 1887720),
+    ("proton_63", 1580130),
+    ("proton_513", 1420170),
+];
+
+/// A compatibility tool found in a Steam library or `compatibilitytools.d`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatTool {
+    /// Internal tool name, e.g. `proton_experimental` or `GE-Proton9-20`
+    pub name: String,
+    /// Name shown in the Steam client, e.g. `Proton Experimental`
+    pub display_name: String,
+    /// The tool's directory, containing its `toolmanifest.vdf`
+    pub path: PathBuf,
+    /// App ID for tools distributed through Steam
+    pub app_id: Option<u32>,
+}
+
+impl std::fmt::Display for CompatTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} ({}", self.display_name, self.name)?;
+        if let Some(app_id) = self.app_id {
+            write!(f, ", {app_id}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// `compatibilitytool.vdf`, which custom tools ship to register themselves
+#[derive(Debug, Deserialize)]
+struct CompatToolsVdf {
+    compat_tools: BTreeMap<String, CompatToolEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompatToolEntry {
+    install_path: PathBuf,
+    display_name: String,
+}
+
+/// The parts of a Steam `appmanifest_<appid>.acf` we care about
+#[derive(Debug, Deserialize)]
+struct AppManifest {
+    name: String,
+    installdir: String,
+}
+
+/// An entry in `libraryfolders.vdf`
+///
+/// Current clients write a block per library, older ones just the path.
+/// Other top-level keys (e.g. `TimeNextStatsReport`) parse as plain values and are ignored.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LibraryFolder {
+    Entry { path: PathBuf },
+    Value(String),
+}
+
+/// Steam install roots, the client install path first
+pub fn steam_roots(steam_compat_config: &SteamCompatConfig) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = steam_compat_config
+        .client_install_path
+        .iter()
+        .cloned()
+        .collect();
+    if let Some(home) = dirs::home_dir() {
+        roots.push(home.join(".steam/root"));
+        roots.push(home.join(".local/share/Steam"));
+    }
+    dedup_paths(roots.into_iter().filter(|p| p.is_dir()))
+}
+
+/// Remove duplicate paths, e.g. `~/.steam/root` is usually a symlink to `~/.local/share/Steam`
+fn dedup_paths(paths: impl Iterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let mut seen = Vec::new();
+    let mut unique = Vec::new();
+    for path in paths {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if !seen.contains(&canonical) {
+            seen.push(canonical);
+            unique.push(path);
+        }
+    }
+    unique
+}
+
+/// Parse a `libraryfolders.vdf`, returning each library's `steamapps` directory
+pub fn parse_library_folders(vdf: &str) -> Vec<PathBuf> {
+    let folders: BTreeMap<String, LibraryFolder> = match keyvalues_serde::from_str(vdf) {
+        Ok(folders) => folders,
+        Err(e) => {
+            tracing::warn!("Failed to parse libraryfolders.vdf: {:?}", e);
+            return vec![];
+        }
+    };
+
+    folders
+        .into_iter()
+        // Library entries are keyed by their index
+        .filter(|(key, _)| key.parse::<u32>().is_ok())
+        .map(|(_, folder)| match folder {
+            LibraryFolder::Entry { path } => path,
+            LibraryFolder::Value(path) => PathBuf::from(path),
+        })
+        .map(|path| path.join("steamapps"))
+        .collect()
+}
+
+/// Steam library `steamapps` directories
+///
+/// Taken from `STEAM_COMPAT_LIBRARY_PATHS` if Steam set it, otherwise from the
+/// `libraryfolders.vdf` of every Steam install found.
+pub fn library_paths(steam_compat_config: &SteamCompatConfig) -> Vec<PathBuf> {
+    if let Some(paths) = &steam_compat_config.library_paths {
+        return std::env::split_paths(paths).collect();
+    }
+
+    tracing::debug!("STEAM_COMPAT_LIBRARY_PATHS is not set, reading libraryfolders.vdf instead");
+    let libraries = steam_roots(steam_compat_config)
+        .into_iter()
+        .map(|root| root.join("steamapps/libraryfolders.vdf"))
+        .filter_map(|vdf| {
+            let contents = std::fs::read_to_string(&vdf).ok()?;
+            tracing::trace!(?vdf, "Reading Steam library folders");
+            Some(parse_library_folders(&contents))
+        })
+        .flatten();
+    dedup_paths(libraries.filter(|p| p.is_dir()))
+}
+
+/// Directories compatibility tools may be installed in, in search order
+///
+/// - `common` in each Steam library, for official tools like Proton
+/// - `compatibilitytools.d` in each Steam install, for custom tools
+/// - The system-wide `/usr/share/steam/compatibilitytools.d`
+pub fn compat_tool_dirs(steam_compat_config: &SteamCompatConfig) -> Vec<PathBuf> {
+    let libraries = library_paths(steam_compat_config)
+        .into_iter()
+        .map(|lib| lib.join("common"));
+    let custom = steam_roots(steam_compat_config)
+        .into_iter()
+        .map(|root| root.join("compatibilitytools.d"));
+    libraries
+        .chain(custom)
+        .chain(std::iter::once(
+            Path::new(SYSTEM_COMPAT_TOOLS_DIR).to_path_buf(),
+        ))
+        .collect()
+}
+
+fn dir_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Index the tools in a Steam library's `common` directory
+///
+/// Official Proton releases are named after their app manifest,
+/// anything else with a `toolmanifest.vdf` is indexed by its directory name.
+fn index_library_tools(steamapps: &Path) -> Vec<CompatTool> {
+    let mut tools: Vec<CompatTool> = OFFICIAL_TOOLS
+        .iter()
+        .filter_map(|(name, app_id)| {
+            let manifest = steamapps.join(format!("appmanifest_{app_id}.acf"));
+            let manifest: AppManifest =
+                keyvalues_serde::from_str(&std::fs::read_to_string(manifest).ok()?).ok()?;
+            Some(CompatTool {
+                name: name.to_string(),
+                display_name: manifest.name,
+                path: steamapps.join("common").join(manifest.installdir),
+                app_id: Some(*app_id),
+            })
+        })
+        .collect();
+
+    let others: Vec<CompatTool> = std::fs::read_dir(steamapps.join("common"))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.join("toolmanifest.vdf").is_file())
+        .filter(|p| !tools.iter().any(|t| &t.path == p))
+        .map(|path| CompatTool {
+            name: dir_name(&path),
+            display_name: dir_name(&path),
+            path,
+            app_id: None,
+        })
+        .collect();
+    tools.extend(others);
+    tools
+}
+
+/// Index the custom tools in a `compatibilitytools.d` directory
+fn index_custom_tools(tools_dir: &Path) -> Vec<CompatTool> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(tools_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    entries.sort();
+
+    entries
+        .into_iter()
+        .flat_map(|dir| {
+            let vdf = std::fs::read_to_string(dir.join("compatibilitytool.vdf"))
+                .ok()
+                .and_then(|c| match keyvalues_serde::from_str::<CompatToolsVdf>(&c) {
+                    Ok(vdf) => Some(vdf),
+                    Err(e) => {
+                        tracing::debug!(?dir, "Failed to parse compatibilitytool.vdf: {:?}", e);
+                        None
+                    }
+                });
+            match vdf {
+                Some(vdf) => vdf
+                    .compat_tools
+                    .into_iter()
+                    .map(|(name, entry)| CompatTool {
+                        name,
+                        display_name: entry.display_name,
+                        path: dir.join(entry.install_path),
+                        app_id: None,
+                    })
+                    .collect(),
+                None if dir.join("toolmanifest.vdf").is_file() => vec![CompatTool {
+                    name: dir_name(&dir),
+                    display_name: dir_name(&dir),
+                    path: dir,
+                    app_id: None,
+                }],
+                None => vec![],
+            }
+        })
+        .collect()
+}
+
+/// Index every compatibility tool installed, in the same order as `compat_tool_dirs()`
+pub fn index_compat_tools(steam_compat_config: &SteamCompatConfig) -> Vec<CompatTool> {
+    let libraries = library_paths(steam_compat_config)
+        .into_iter()
+        .flat_map(|lib| index_library_tools(&lib));
+    let custom = steam_roots(steam_compat_config)
+        .into_iter()
+        .map(|root| root.join("compatibilitytools.d"))
+        .chain(std::iter::once(PathBuf::from(SYSTEM_COMPAT_TOOLS_DIR)))
+        .flat_map(|dir| index_custom_tools(&dir));
+    libraries.chain(custom).collect()
+}
+
+/// Find a tool by its directory name, display name, internal name or app ID
+pub fn find_compat_tool<'a>(tools: &'a [CompatTool], query: &str) -> Option<&'a CompatTool> {
+    let app_id = query.parse::<u32>().ok();
+    tools
+        .iter()
+        .find(|t| t.path.file_name().is_some_and(|n| n == query))
+        .or_else(|| tools.iter().find(|t| t.display_name == query))
+        .or_else(|| tools.iter().find(|t| t.name == query))
+        .or_else(|| {
+            tools
+                .iter()
+                .find(|t| app_id.is_some() && t.app_id == app_id)
+        })
+        .or_else(|| {
+            tools
+                .iter()
+                .find(|t| t.display_name.eq_ignore_ascii_case(query))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_library_folders() {
+        let vdf = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/user/.local/share/Steam"
+		"label"		""
+		"contentid"		"1234567890"
+		"totalsize"		"0"
+		"apps"
+		{
+			"228980"		"123456"
+		}
+	}
+	"1"
+	{
+		"path"		"/mnt/games/SteamLibrary"
+		"label"		"Games"
+		"apps"
+		{
+		}
+	}
+}
+"#;
+        assert_eq!(
+            parse_library_folders(vdf),
+            vec![
+                PathBuf::from("/home/user/.local/share/Steam/steamapps"),
+                PathBuf::from("/mnt/games/SteamLibrary/steamapps"),
+            ]
+        );
+
+        let legacy = r#"
+"LibraryFolders"
+{
+	"TimeNextStatsReport"		"1700000000"
+	"ContentStatsID"		"-1234"
+	"1"		"/mnt/games/SteamLibrary"
+}
+"#;
+        assert_eq!(
+            parse_library_folders(legacy),
+            vec![PathBuf::from("/mnt/games/SteamLibrary/steamapps")]
+        );
+    }
+
+    #[test]
+    fn test_index_and_find_compat_tools() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let steamapps = temp_dir.path().join("steamapps");
+        let proton = steamapps.join("common/Proton - Experimental");
+        std::fs::create_dir_all(&proton).unwrap();
+        std::fs::write(proton.join("toolmanifest.vdf"), "").unwrap();
+        std::fs::write(
+            steamapps.join("appmanifest_1493710.acf"),
+            r#""AppState"
+{
+	"appid"		"1493710"
+	"name"		"Proton Experimental"
+	"installdir"		"Proton - Experimental"
+}
+"#,
+        )
+        .unwrap();
+
+        let tools_dir = temp_dir.path().join("compatibilitytools.d");
+        let ge = tools_dir.join("GE-Proton9-20");
+        std::fs::create_dir_all(&ge).unwrap();
+        std::fs::write(
+            ge.join("compatibilitytool.vdf"),
+            r#""compatibilitytools"
+{
+  "compat_tools"
+  {
+    "GE-Proton9-20" // Internal name of this tool
+    {
+      "install_path" "."
+      "display_name" "GE-Proton9-20 (custom)"
+      "from_oslist"  "windows"
+      "to_oslist"    "linux"
+    }
+  }
+}
+"#,
+        )
+        .unwrap();
+
+        let tools: Vec<CompatTool> = index_library_tools(&steamapps)
+            .into_iter()
+            .chain(index_custom_tools(&tools_dir))
+            .collect();
+        assert_eq!(tools.len(), 2);
+
+        let find = |query: &str| find_compat_tool(&tools, query).map(|t| t.path.clone());
+        assert_eq!(find("Proton - Experimental"), Some(proton.clone()));
+        assert_eq!(find("proton_experimental"), Some(proton.clone()));
+        assert_eq!(find("1493710"), Some(proton.clone()));
+        assert_eq!(find("proton experimental"), Some(proton));
+        assert_eq!(find("GE-Proton9-20 (custom)"), Some(ge.join(".")));
+        assert_eq!(find("GE-Proton9-20"), Some(ge.join(".")));
+        assert_eq!(find("Proton 9.0"), None);
+    }
+}
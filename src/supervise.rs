@@ -0,0 +1,313 @@
+//! Game process supervision
+//!
+//! Games don't always stay a single process: Electron forks GPU, renderer and utility processes,
+//! and launchers exit as soon as they've started the real game. Steam tracks the game through
+//! the process it launched (us), so we stay around until the whole process tree is gone.
+//!
+//! To do that, Boson becomes a child subreaper so orphaned descendants are reparented to it,
+//! starts the game in its own process group, and forwards termination signals to that group.
+//! Descendants still running a grace period after a termination signal are killed.
+use std::{
+    collections::BTreeMap,
+    os::unix::process::{CommandExt, ExitStatusExt},
+    process::{Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use stable_eyre::Result;
+
+use crate::logging;
+
+/// Default time descendants get to exit after a termination signal, in seconds
+pub const DEFAULT_KILL_GRACE_PERIOD: u64 = 10;
+
+/// Signals forwarded to the game's process group
+const FORWARDED_SIGNALS: [libc::c_int; 3] = [libc::SIGTERM, libc::SIGINT, libc::SIGHUP];
+
+/// How often the process tree is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Parse the parent PID and state out of a `/proc/<pid>/stat` line
+///
+/// The command name is in parentheses and can contain anything, including spaces and `)`,
+/// so the fields are read after the last `)`.
+pub fn parse_stat(stat: &str) -> Option<(i32, char)> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    let mut fields = fields.split_whitespace();
+    let state = fields.next()?.chars().next()?;
+    let ppid = fields.next()?.parse().ok()?;
+    Some((ppid, state))
+}
+
+/// Find the running descendants of `root`, given the parent PID and state of every process
+///
+/// Zombies are left out, they're already gone and only waiting to be reaped.
+pub fn descendants(root: i32, processes: &BTreeMap<i32, (i32, char)>) -> Vec<i32> {
+    let mut found = Vec::new();
+    let mut parents = vec![root];
+    while let Some(parent) = parents.pop() {
+        for (&pid, &(ppid, state)) in processes {
+            if ppid == parent && pid != root {
+                parents.push(pid);
+                if state != 'Z' {
+                    found.push(pid);
+                }
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Read the parent PID and state of every process from `/proc`
+fn process_table() -> BTreeMap<i32, (i32, char)> {
+    std::fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let pid: i32 = e.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(e.path().join("stat")).ok()?;
+            Some((pid, parse_stat(&stat)?))
+        })
+        .collect()
+}
+
+/// Make this process a child subreaper, so orphaned descendants are reparented to us
+/// instead of init and we can keep track of them
+fn become_subreaper() {
+    // SAFETY: PR_SET_CHILD_SUBREAPER only takes an integer flag
+    let ret = unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0) };
+    if ret != 0 {
+        tracing::warn!(
+            "Could not become a child subreaper, processes left behind by the game won't be tracked: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Block the forwarded signals and handle them on a dedicated thread instead,
+/// which sends them on to the game's process group
+///
+/// This has to be called before any other thread is started, so they all inherit the mask.
+/// The spawned game gets an empty signal mask again from `std::process`.
+fn forward_signals(pgid: Arc<AtomicI32>, received: Arc<AtomicI32>) -> Result<()> {
+    // SAFETY: the signal set is initialized by sigemptyset before use
+    let set = unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        for signal in FORWARDED_SIGNALS {
+            libc::sigaddset(&mut set, signal);
+        }
+        let ret = libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+        if ret != 0 {
+            return Err(stable_eyre::eyre::eyre!(
+                "Could not block termination signals: {}",
+                std::io::Error::from_raw_os_error(ret)
+            ));
+        }
+        set
+    };
+
+    std::thread::Builder::new()
+        .name("signal-forwarder".to_string())
+        .spawn(move || loop {
+            let mut signal = 0;
+            // SAFETY: set is a valid signal set blocked on every thread
+            if unsafe { libc::sigwait(&set, &mut signal) } != 0 {
+                continue;
+            }
+            received.store(signal, Ordering::SeqCst);
+            let pgid = pgid.load(Ordering::SeqCst);
+            if pgid > 0 {
+                tracing::info!(signal, pgid, "Forwarding signal to the game");
+                // SAFETY: kill has no memory safety requirements
+                unsafe { libc::kill(-pgid, signal) };
+            }
+        })?;
+    Ok(())
+}
+
+/// Name of a signal, e.g. `SIGSEGV`
+fn signal_name(signal: i32) -> String {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        _ => return format!("signal {signal}"),
+    };
+    name.to_string()
+}
+
+/// The exit code to pass on for the game's exit status
+///
+/// Exit codes are passed on as is, deaths by signal become `128 + signal` like shells report them.
+pub fn exit_code(status: &ExitStatus) -> i32 {
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1)
+}
+
+/// Log why the game exited
+fn log_exit(status: &ExitStatus) {
+    if let Some(code) = status.code() {
+        if code == 0 {
+            tracing::info!("Game exited successfully");
+        } else {
+            tracing::warn!("Game exited with code {}", code);
+        }
+    } else if let Some(signal) = status.signal() {
+        tracing::error!(
+            "Game was killed by {}{}",
+            signal_name(signal),
+            if status.core_dumped() {
+                " (core dumped)"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+/// Send a signal to each of the given processes
+fn signal_all(pids: &[i32], signal: libc::c_int) {
+    for &pid in pids {
+        // SAFETY: kill has no memory safety requirements
+        unsafe { libc::kill(pid, signal) };
+    }
+}
+
+/// Run the game and wait for it and every process it leaves behind to exit
+///
+/// Returns the exit status of the game process itself, even if its descendants outlive it.
+/// With per-game logs enabled, the game's output is captured into the log file.
+/// After a termination signal, descendants still running once `grace_period` has passed
+/// are killed.
+pub fn run(cmd: &mut Command, grace_period: Duration) -> Result<ExitStatus> {
+    let pgid = Arc::new(AtomicI32::new(0));
+    let received = Arc::new(AtomicI32::new(0));
+    forward_signals(pgid.clone(), received.clone())?;
+    become_subreaper();
+
+    // Started after the signals are blocked, so they're never delivered to these threads
+    let tee = logging::log_file()
+        .map(|log_file| logging::Tee::capture(cmd, log_file))
+        .transpose()?;
+
+    let child = cmd.process_group(0).spawn()?;
+    // Drop our copies of the output pipes, so capturing ends once the game's processes are gone
+    cmd.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+    let child_pid = child.id() as i32;
+    pgid.store(child_pid, Ordering::SeqCst);
+    tracing::debug!(pid = child_pid, "Game started in its own process group");
+
+    let own_pid = std::process::id() as i32;
+    let mut status = None;
+    let mut kill_deadline = None;
+    let mut killed = false;
+    let mut last_remaining = Vec::new();
+    loop {
+        // Reap the game and any orphans reparented to us
+        loop {
+            let mut raw_status = 0;
+            // SAFETY: raw_status is a valid pointer to write the status into
+            let pid = unsafe { libc::waitpid(-1, &mut raw_status, libc::WNOHANG) };
+            if pid <= 0 {
+                break;
+            }
+            if pid == child_pid {
+                let exit_status = ExitStatus::from_raw(raw_status);
+                log_exit(&exit_status);
+                status = Some(exit_status);
+            } else {
+                tracing::trace!(pid, "Reaped descendant process");
+            }
+        }
+
+        let remaining = descendants(own_pid, &process_table());
+        if let Some(status) = status {
+            if remaining.is_empty() {
+                if let Some(tee) = tee {
+                    tee.finish();
+                }
+                return Ok(status);
+            }
+        }
+
+        if kill_deadline.is_none() && received.load(Ordering::SeqCst) != 0 {
+            tracing::debug!(?grace_period, "Waiting for the game to exit");
+            kill_deadline = Some(Instant::now() + grace_period);
+        }
+        if kill_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            if !killed {
+                tracing::warn!(
+                    pids = ?remaining,
+                    "Game processes still running {:?} after being asked to exit, killing them",
+                    grace_period
+                );
+                killed = true;
+            }
+            // Keep at it, in case anything was forked in the meantime
+            signal_all(&remaining, libc::SIGKILL);
+        }
+
+        if status.is_some() && remaining != last_remaining {
+            tracing::debug!(pids = ?remaining, "Game exited, waiting for remaining processes");
+        }
+        last_remaining = remaining;
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        // Raw wait statuses: exit code in the second byte, signal in the low 7 bits
+        assert_eq!(exit_code(&ExitStatus::from_raw(0)), 0);
+        assert_eq!(exit_code(&ExitStatus::from_raw(3 << 8)), 3);
+        assert_eq!(exit_code(&ExitStatus::from_raw(libc::SIGSEGV)), 139);
+        assert_eq!(exit_code(&ExitStatus::from_raw(libc::SIGKILL)), 137);
+        assert!(ExitStatus::from_raw(libc::SIGABRT | 0x80).core_dumped());
+        assert_eq!(signal_name(libc::SIGABRT), "SIGABRT");
+    }
+
+    #[test]
+    fn test_descendants_from_stat() {
+        assert_eq!(
+            parse_stat("4242 (Game (Main) Thread) S 4200 4242 4200 0 -1"),
+            Some((4200, 'S'))
+        );
+        assert_eq!(parse_stat("garbage"), None);
+
+        let processes: BTreeMap<i32, (i32, char)> = [
+            (1, (0, 'S')),
+            (100, (1, 'S')), // boson
+            (101, (100, 'S')),
+            (102, (101, 'R')),
+            (103, (102, 'Z')),
+            (104, (102, 'S')),
+            (200, (1, 'S')),
+        ]
+        .into();
+        assert_eq!(descendants(100, &processes), vec![101, 102, 104]);
+        assert!(descendants(200, &processes).is_empty());
+    }
+}